@@ -0,0 +1,651 @@
+use anyhow::{anyhow, Result};
+use clap::{Args, Parser, Subcommand};
+use rand::{
+  thread_rng,
+  RngCore,
+};
+use std::{
+  collections::BTreeMap,
+  path::{Path, PathBuf},
+};
+
+use witness_audio_randomizer::core::{
+  config::Config,
+  constraints::Constraints,
+  discovery,
+  discovery::InstallSource,
+  plando::Plando,
+  randomizer,
+  randomizer::CustomShare,
+  seed,
+  settings,
+  settings::Settings,
+  sources,
+  spoiler,
+  spoiler::SpoilerLog,
+  witness_data,
+  witness_data::{DataStore, SoundDestination, SoundInsertionMap, SubsInsertionMap, Subtitle, SubtitleFallback},
+};
+
+// ---------------------------------------------------------------------------------------------------
+
+/// Command line interface to the Witness audio log randomizer
+#[derive(Debug, Parser)]
+#[command(name = "witness-rando", version)]
+pub struct Cli {
+  /// The Witness install directory (defaults to the value in config.json)
+  #[arg(long, global = true, conflicts_with = "install")]
+  witness_dir: Option<PathBuf>,
+
+  /// Name of a registered install to operate on, instead of a directory
+  #[arg(long, global = true)]
+  install: Option<String>,
+
+  /// What custom logs show in the languages they have no subtitles for: `english` or `blank`
+  /// (defaults to the value in config.json)
+  #[arg(long, global = true, value_name = "FALLBACK")]
+  subtitle_fallback: Option<SubtitleFallback>,
+
+  /// Skip audio logs without subtitles and audio files that fail to insert, instead of stopping.
+  /// `.sub` files that can't be inserted always stop the run before the game is modified
+  #[arg(long, global = true)]
+  lenient: bool,
+
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+  /// Unpack the game's data files and back up the files modified by the randomizer
+  Backup,
+
+  /// Randomly insert the audio logs from a directory into the game
+  Randomize(Box<RandomizeArgs>),
+
+  /// Insert audio logs into the slots given by a plando file, without any randomization
+  Plando {
+    /// JSON file mapping slot subtitle keys to the .ogg file to put in each slot (see `list-slots`)
+    file: PathBuf,
+  },
+
+  /// Extract all audio logs and subtitles from the game to a directory
+  Dump {
+    /// Directory the audio logs are extracted to
+    dest_dir: PathBuf,
+  },
+
+  /// Restore the backed up data files, returning the game to its original state
+  Restore,
+
+  /// Insert a single audio log into the game as the mountaintop log
+  TestInsert {
+    /// The .ogg file to insert; a .sub file (or .srt, .vtt or .txt transcript) with the same name is
+    /// inserted alongside it
+    ogg_file: PathBuf,
+  },
+
+  /// Replace every audio log in the game with The Secret of Psalm 46
+  Lucky,
+
+  /// Show which audio log was placed in which slot by the last randomization
+  Spoiler {
+    /// Print the spoiler log as JSON
+    #[arg(long)]
+    json: bool,
+  },
+
+  /// List the game's audio log slots and where their files are stored
+  ListSlots {
+    /// Print the slots as JSON
+    #[arg(long)]
+    json: bool,
+  },
+
+  /// List the packs (subfolders) in an audio logs directory
+  Packs {
+    /// Directory containing the audio logs (defaults to the configured logs directory)
+    #[arg(long)]
+    logs_dir: Option<PathBuf>,
+  },
+
+  /// Manage the registered installs of The Witness
+  Installs {
+    #[command(subcommand)]
+    command: InstallsCommand,
+  },
+}
+
+#[derive(Debug, Subcommand)]
+enum InstallsCommand {
+  /// List the registered installs and the state of their backups
+  List,
+
+  /// Find installs through the Steam, Epic and GOG metadata and register any new ones
+  Detect,
+
+  /// Register an install manually
+  Add {
+    name: String,
+    path: PathBuf,
+  },
+
+  /// Unregister an install. The game files and backups are left untouched.
+  Remove {
+    name: String,
+  },
+}
+
+#[derive(Debug, Args)]
+struct RandomizeArgs {
+  /// Settings string from an earlier run, which sets the seed and all the options below it
+  #[arg(long, conflicts_with_all = [
+    "seed", "daily", "salt", "constraints", "fill", "vanilla", "mix", "include", "exclude", "packs",
+    "disabled_packs", "areas", "max_per_area", "spread_packs",
+  ])]
+  settings: Option<Settings>,
+
+  /// Seed value used for randomization (a random seed is generated if omitted)
+  #[arg(long, conflicts_with = "daily")]
+  seed: Option<String>,
+
+  /// Use the daily seed for the current UTC date, which is the same for everyone on the same day
+  #[arg(long)]
+  daily: bool,
+
+  /// Salt mixed into the daily seed, so that a group gets its own daily randomization
+  #[arg(long, requires = "daily")]
+  salt: Option<String>,
+
+  /// Directory containing the .ogg (and optional .sub, .srt, .vtt or .txt) files or packs to insert,
+  /// or a single pack
+  #[arg(long)]
+  logs_dir: Option<PathBuf>,
+
+  /// Placement constraints file (defaults to constraints.json in the logs directory, if present)
+  #[arg(long)]
+  constraints: Option<PathBuf>,
+
+  /// Reuse audio logs so that every slot is replaced when there are fewer logs than slots
+  #[arg(long)]
+  fill: bool,
+
+  /// Shuffle the game's own audio logs between their slots instead of inserting custom logs
+  #[arg(long, conflicts_with_all = ["logs_dir", "mix"])]
+  vanilla: bool,
+
+  /// Fill the slots not given to custom logs with the game's own logs. SHARE is the number of custom
+  /// logs to place, a percentage of the slots (e.g. 30%), or `all`
+  #[arg(long, value_name = "SHARE")]
+  mix: Option<CustomShare>,
+
+  /// Only use the logs whose path in the logs directory matches one of these glob patterns
+  #[arg(long, value_name = "GLOB")]
+  include: Vec<String>,
+
+  /// Skip the logs whose path in the logs directory matches one of these glob patterns
+  #[arg(long, value_name = "GLOB")]
+  exclude: Vec<String>,
+
+  /// Only use the logs from these packs (subfolders of the logs directory) and the top-level logs
+  #[arg(long = "pack", value_name = "PACK")]
+  packs: Vec<String>,
+
+  /// Skip the logs from these packs
+  #[arg(long = "disable-pack", value_name = "PACK")]
+  disabled_packs: Vec<String>,
+
+  /// Only randomize the slots in these areas (see `list-slots`)
+  #[arg(long = "area", value_name = "AREA")]
+  areas: Vec<String>,
+
+  /// Place at most this many custom logs in each area
+  #[arg(long, value_name = "COUNT")]
+  max_per_area: Option<usize>,
+
+  /// Spread the logs of each pack across as many areas as possible
+  #[arg(long)]
+  spread_packs: bool,
+}
+
+pub fn run(args: Cli, mut config: Config) -> Result<()> {
+  let insert = InsertOptions {
+    fallback: args.subtitle_fallback.unwrap_or(config.subtitle_fallback),
+    strict: !args.lenient,
+  };
+
+  // Only the commands that work on an install need to find it
+  let witness_dir = |config: &Config| -> Result<PathBuf> {
+    let witness_dir = match (&args.witness_dir, &args.install) {
+      (Some(dir), _) => dir.clone(),
+      (None, Some(name)) => config.install_dir(name)?,
+      (None, None) => config.witness_dir.clone(),
+    };
+
+    if !witness_data::witness_dir_is_okay(&witness_dir) {
+      return Err(anyhow!("Could not find The Witness data files in {:?}", witness_dir));
+    }
+    Ok(witness_dir)
+  };
+
+  match args.command {
+    Command::Installs { command } => installs(command, &mut config),
+    Command::Packs { logs_dir } => list_packs(&logs_dir.unwrap_or(config.logs_dir)),
+    Command::ListSlots { json } => list_slots(json),
+    Command::Backup => backup(&witness_dir(&config)?),
+    Command::Randomize(randomize_args) => randomize(&witness_dir(&config)?, &config.logs_dir, *randomize_args, insert),
+    Command::Plando { file } => plando(&witness_dir(&config)?, &file, insert),
+    Command::Dump { dest_dir } => dump(&witness_dir(&config)?, &dest_dir),
+    Command::Restore => restore(&witness_dir(&config)?),
+    Command::TestInsert { ogg_file } => test_insert(&witness_dir(&config)?, &ogg_file),
+    Command::Lucky => lucky(&witness_dir(&config)?, insert),
+    Command::Spoiler { json } => show_spoiler(&witness_dir(&config)?, json),
+  }
+}
+
+// ---------------------------------------------------------------------------------------------------
+
+fn backup(witness_dir: &Path) -> Result<()> {
+  if witness_data::data_needs_unpacking(witness_dir) {
+    println!("Unpacking data files");
+    witness_data::unpack_witness_data(witness_dir)?;
+  }
+
+  if witness_data::data_needs_backing_up(witness_dir) {
+    println!("Backing up data files");
+    witness_data::create_audio_backup(witness_dir)?;
+  }
+
+  if witness_data::subtitles_backup_is_legacy(witness_dir) {
+    println!("Backing up the subtitles of the languages missing from the existing backup");
+    witness_data::create_subtitles_backup(witness_dir)?;
+  } else if witness_data::subtitles_need_backing_up(witness_dir) {
+    println!("Backing up subtitles file");
+    witness_data::create_subtitles_backup(witness_dir)?;
+  }
+
+  println!("Data files backed up and unpacked");
+  Ok(())
+}
+
+fn randomize(witness_dir: &Path, default_logs_dir: &Path, args: RandomizeArgs, insert: InsertOptions) -> Result<()> {
+  ensure_backed_up(witness_dir)?;
+
+  let logs_dir = args.logs_dir.clone().unwrap_or_else(|| default_logs_dir.to_owned());
+  let settings = match args.settings.clone() {
+    Some(settings) => settings,
+    None => settings_from_args(&logs_dir, args)?,
+  };
+
+  if !settings.vanilla && !logs_dir.exists() {
+    return Err(anyhow!("Audio logs directory {:?} does not exist", logs_dir));
+  }
+
+  let originals_dir = if settings.vanilla || settings.mix.is_some() {
+    println!("Extracting original audio logs");
+    Some(witness_data::extract_original_logs(witness_dir)?)
+  } else {
+    None
+  };
+
+  let result = match &originals_dir {
+    Some(originals_dir) if settings.vanilla => randomize_from(witness_dir, originals_dir, None, settings, insert),
+    _ => randomize_from(witness_dir, &logs_dir, originals_dir.clone(), settings, insert),
+  };
+
+  // A failed cleanup only leaves temporary files behind, so it must not hide how the run went
+  if originals_dir.is_some() {
+    if let Err(err) = witness_data::remove_original_logs(witness_dir) {
+      log::warn!("Could not remove the extracted audio logs: {:?}", err);
+    }
+  }
+  result
+}
+
+fn settings_from_args(logs_dir: &Path, args: RandomizeArgs) -> Result<Settings> {
+  let seed_string = if args.daily {
+    let date = seed::UtcDate::today();
+    println!("Daily seed for {} (UTC)", date);
+    log::info!("Using the daily seed for {} (UTC)", date);
+    seed::daily_seed_string(date, args.salt.as_deref())
+  } else {
+    args.seed.unwrap_or_else(|| format!("{:X}", thread_rng().next_u64()))
+  };
+
+  // The constraints file in the logs directory does not apply when the original logs are shuffled
+  let constraints = match &args.constraints {
+    Some(path) => Constraints::load(path)?,
+    None if args.vanilla => Constraints::default(),
+    None => Constraints::for_logs_dir(logs_dir)?,
+  };
+
+  Ok(Settings {
+    vanilla: args.vanilla,
+    fill: args.fill,
+    mix: args.mix.map(|mix| mix.to_string()),
+    include: args.include,
+    exclude: args.exclude,
+    packs: args.packs,
+    disabled_packs: args.disabled_packs,
+    areas: args.areas,
+    max_per_area: args.max_per_area,
+    spread_packs: args.spread_packs,
+    constraints,
+    ..Settings::new(&seed_string)
+  })
+}
+
+fn randomize_from(
+  witness_dir: &Path,
+  logs_dir: &Path,
+  originals_dir: Option<PathBuf>,
+  mut settings: Settings,
+  insert: InsertOptions,
+) -> Result<()> {
+  let options = settings.options(originals_dir)?;
+
+  let fingerprint = randomizer::fingerprint(logs_dir, &options.filter)?;
+  if settings.fingerprint.is_some_and(|expected| expected != fingerprint) {
+    return Err(anyhow!("The audio logs in {:?} are not the ones these settings were made with", logs_dir));
+  }
+  settings.fingerprint = Some(fingerprint);
+
+  let seed_string = settings.seed.clone();
+  println!("Seed: {}", seed_string);
+  log::info!("Randomizing with seed {:?} (seed scheme v{})", seed_string, seed::SEED_SCHEME_VERSION);
+
+  let placements = randomizer::place(seed::seed_from_str(&seed_string), logs_dir, &options)?;
+  randomizer::check_insertion(witness_dir, &placements, insert.fallback, insert.strict)?;
+  let (logs, subs) = randomizer::insertion_maps(&placements);
+
+  let check = settings::check_words(&placements)?;
+  println!("Settings: {}", settings);
+  println!("Check: {}", check);
+
+  if !options.areas.is_empty() {
+    let mut per_area: BTreeMap<&str, usize> = BTreeMap::new();
+    for placement in &placements {
+      *per_area.entry(placement.slot.area.as_deref().unwrap_or("Unknown area")).or_default() += 1;
+    }
+    for (area, count) in per_area {
+      println!("{}: {} log(s)", area, count);
+    }
+  }
+
+  let mut spoiler = SpoilerLog::new(Some(&seed_string), &placements);
+  spoiler.settings = Some(settings.to_string());
+  spoiler.check = Some(check);
+  insert_logs(witness_dir, logs, subs, spoiler, insert)
+}
+
+fn plando(witness_dir: &Path, file: &Path, insert: InsertOptions) -> Result<()> {
+  ensure_backed_up(witness_dir)?;
+
+  let placements = Plando::load(file)?.placements()?;
+  println!("Placing {} audio log(s) from {:?}", placements.len(), file);
+
+  randomizer::check_insertion(witness_dir, &placements, insert.fallback, insert.strict)?;
+
+  let (logs, subs) = randomizer::insertion_maps(&placements);
+  insert_logs(witness_dir, logs, subs, SpoilerLog::new(None, &placements), insert)
+}
+
+fn dump(witness_dir: &Path, dest_dir: &Path) -> Result<()> {
+  ensure_backed_up(witness_dir)?;
+
+  let logs = DataStore::get_logs()?;
+  let subs = witness_data::load_subtitles(witness_dir)?;
+
+  std::fs::create_dir_all(dest_dir)?;
+  witness_data::dump_logs(witness_dir, dest_dir, &logs, &subs)?;
+
+  println!("Audio logs dumped to {:?}", dest_dir);
+  Ok(())
+}
+
+fn restore(witness_dir: &Path) -> Result<()> {
+  println!("Restoring subtitles");
+  witness_data::restore_subtitles_backup(witness_dir)?;
+
+  println!("Restoring audio files");
+  witness_data::restore_audio_backup(witness_dir)?;
+
+  spoiler::remove_spoiler(witness_dir)?;
+
+  println!("Data files restored successfully");
+  Ok(())
+}
+
+fn test_insert(witness_dir: &Path, ogg_file: &Path) -> Result<()> {
+  ensure_backed_up(witness_dir)?;
+
+  if !ogg_file.is_file() {
+    return Err(anyhow!("Test file {:?} does not exist", ogg_file));
+  }
+
+  witness_data::insert_on_mountaintop(witness_dir, ogg_file)?;
+
+  println!("File inserted successfully");
+  Ok(())
+}
+
+fn lucky(witness_dir: &Path, insert: InsertOptions) -> Result<()> {
+  ensure_backed_up(witness_dir)?;
+
+  let subs_data = witness_data::load_subtitles(witness_dir)?;
+
+  let secret_of_psalm_46_path = witness_data::psalm_46_path(witness_dir);
+  let secret_of_psalm_46_subs = subs_data.iter()
+    .find(|Subtitle {key, val: _val}| key == "psalm46")
+    .map(|subtitle| subtitle.val.clone())
+    .unwrap_or_default();
+  let subs_file = std::env::current_dir()?.join("psalm46.sub");
+
+  let placements = randomizer::feeling_lucky(&secret_of_psalm_46_path, &subs_file)?;
+  std::fs::write(&subs_file, secret_of_psalm_46_subs)?;
+
+  if let Err(err) = randomizer::check_insertion(witness_dir, &placements, insert.fallback, insert.strict) {
+    let _ = std::fs::remove_file(&subs_file);
+    return Err(err);
+  }
+
+  let (logs, subs) = randomizer::insertion_maps(&placements);
+  let result = insert_logs(witness_dir, logs, subs, SpoilerLog::new(None, &placements), insert);
+
+  let _ = std::fs::remove_file(&subs_file);
+  result
+}
+
+fn show_spoiler(witness_dir: &Path, json: bool) -> Result<()> {
+  let spoiler = spoiler::read_spoiler(witness_dir)?;
+
+  if json {
+    println!("{}", serde_json::to_string_pretty(&spoiler)?);
+  } else {
+    print!("{}", spoiler.to_text());
+  }
+  Ok(())
+}
+
+fn list_slots(json: bool) -> Result<()> {
+  let slots = DataStore::get_logs()?;
+
+  if json {
+    println!("{}", serde_json::to_string_pretty(&slots)?);
+    return Ok(());
+  }
+
+  // Areas only come from the slot data overlays, so they are only listed when some slot has one
+  let with_areas = slots.iter().any(|slot| slot.area.is_some());
+
+  if with_areas {
+    println!("{:<36}  {:<16}  FILE", "SLOT", "AREA");
+  } else {
+    println!("{:<36}  FILE", "SLOT");
+  }
+
+  for slot in &slots {
+    let file = match &slot.package {
+      Some(package) => format!("{}/{}", package.display(), slot.filename.display()),
+      None          => format!("data-pc/{}", slot.filename.display()),
+    };
+
+    if with_areas {
+      println!("{:<36}  {:<16}  {}", slot.subtitle, slot.area.as_deref().unwrap_or("-"), file);
+    } else {
+      println!("{:<36}  {}", slot.subtitle, file);
+    }
+  }
+  Ok(())
+}
+
+fn list_packs(logs_dir: &Path) -> Result<()> {
+  let packs = sources::list_packs(logs_dir)?;
+
+  if packs.is_empty() {
+    println!("No packs in {:?}", logs_dir);
+  }
+  for (pack, count) in packs {
+    println!("{} ({} logs)", pack, count);
+  }
+  Ok(())
+}
+
+fn installs(command: InstallsCommand, config: &mut Config) -> Result<()> {
+  match command {
+    InstallsCommand::List => {
+      if config.installs.is_empty() {
+        println!("No installs registered, run `witness-rando installs detect` or `witness-rando installs add`");
+      }
+
+      for install in &config.installs {
+        let state = install.backup_state();
+        let backup = if !witness_data::witness_dir_is_okay(&install.path) {
+          "install missing"
+        } else if state.is_complete() {
+          "backed up"
+        } else {
+          "not backed up"
+        };
+
+        println!("{}\t{}\t{}\t{}", install.name, install.source, backup, install.path.display());
+      }
+      return Ok(());
+    },
+    InstallsCommand::Detect => {
+      let added = config.register_detected(discovery::find_installs());
+
+      if added.is_empty() {
+        println!("No new installs found");
+      }
+      for name in added {
+        println!("Registered `{}` ({})", name, config.install_dir(&name)?.display());
+      }
+    },
+    InstallsCommand::Add { name, path } => {
+      config.add_install(&name, &path, InstallSource::Manual)?;
+      println!("Registered `{}`", name);
+    },
+    InstallsCommand::Remove { name } => {
+      config.remove_install(&name)?;
+      println!("Unregistered `{}`", name);
+    },
+  }
+
+  config.save()
+}
+
+// ---------------------------------------------------------------------------------------------------
+
+fn ensure_backed_up(witness_dir: &Path) -> Result<()> {
+  if !witness_data::backup_state(witness_dir).is_complete() {
+    return Err(anyhow!("Data files have not been backed up yet, run `witness-rando backup` first"));
+  }
+
+  Ok(())
+}
+
+// The game files have already been modified at this point, so failing to write the spoiler log is not
+// treated as a failure of the whole run
+fn save_spoiler(witness_dir: &Path, spoiler: SpoilerLog) {
+  match spoiler::write_spoiler(witness_dir, &spoiler) {
+    Ok(()) => println!("Spoiler log written to {}", witness_dir.display()),
+    Err(err) => log::error!("Could not write spoiler log: {:?}", err),
+  }
+}
+
+// Restores the data files from the backup after a failed insertion in strict mode, so the game is
+// not left half randomized. The spoiler log of the previous run no longer applies either way.
+fn roll_back(witness_dir: &Path, err: anyhow::Error) -> anyhow::Error {
+  println!("Insertion failed, restoring the data files from the backup");
+
+  let restored = witness_data::restore_backups(witness_dir)
+    .and_then(|()| spoiler::remove_spoiler(witness_dir));
+
+  match restored {
+    Ok(()) => err.context("The audio logs could not be inserted, so the game's data files were restored from the backup"),
+    Err(restore_err) => {
+      log::error!("Could not restore the data files: {:?}", restore_err);
+      err.context(
+        "The audio logs could not be inserted, and the game's data files could not be restored from the \
+         backup: run `witness-rando restore` before playing"
+      )
+    },
+  }
+}
+
+// How audio logs are inserted into the game
+#[derive(Debug, Clone, Copy)]
+struct InsertOptions {
+  fallback: SubtitleFallback,
+
+  // Check every log before the game is modified, and stop at the first failed insertion, restoring
+  // the data files from the backup
+  strict: bool,
+}
+
+// Inserts the logs and their subtitles, and writes the spoiler log for them unless the run was rolled
+// back
+fn insert_logs(
+  witness_dir: &Path,
+  logs: SoundInsertionMap,
+  subs: SubsInsertionMap,
+  spoiler: SpoilerLog,
+  insert: InsertOptions,
+) -> Result<()> {
+  let mut error_count = 0;
+
+  for (key, vals) in logs {
+    let dest = match &key {
+      SoundDestination::Package(pkg) => pkg.to_string_lossy().to_string(),
+      SoundDestination::Root => String::from("data-pc/"),
+    };
+    println!("Randomizing logs in {}", dest);
+
+    if let Err(err) = witness_data::insert_sound_files(vals, key, witness_dir) {
+      if insert.strict {
+        return Err(roll_back(witness_dir, err.context(format!("Could not insert the audio logs in {}", dest))));
+      }
+      log::error!("Sound insertion failed: {:?}", err);
+      error_count += 1;
+    }
+  }
+
+  println!("Updating subtitles");
+  if let Err(err) = witness_data::insert_subtitles(witness_dir, subs, insert.fallback) {
+    if insert.strict {
+      return Err(roll_back(witness_dir, err.context("Could not insert the subtitles")));
+    }
+    log::error!("Subtitles insertion failed: {:?}", err);
+    error_count += 1;
+  }
+
+  save_spoiler(witness_dir, spoiler);
+
+  if error_count == 0 {
+    println!("Finished successfully");
+    Ok(())
+  } else {
+    Err(anyhow!("Finished with {} error(s)", error_count))
+  }
+}
@@ -1,41 +1,155 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde::{Serialize, Deserialize};
 use std::{
   fs,
-  path::PathBuf,
+  path::{Path, PathBuf},
+};
+
+use crate::core::{
+  discovery,
+  discovery::{DetectedInstall, InstallSource},
+  witness_data,
+  witness_data::{BackupState, SubtitleFallback},
 };
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
   pub witness_dir: PathBuf,
   pub logs_dir: PathBuf,
+  #[serde(default)]
+  pub installs: Vec<Install>,
+
+  // What custom logs show in the languages they have no subtitles for
+  #[serde(default)]
+  pub subtitle_fallback: SubtitleFallback,
+}
+
+// A named install of The Witness. Backups are stored inside each install's directory, so every
+// registered install has its own backup state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Install {
+  pub name: String,
+  pub path: PathBuf,
+  pub source: InstallSource,
+}
+
+impl Install {
+  pub fn backup_state(&self) -> BackupState {
+    witness_data::backup_state(&self.path)
+  }
 }
 
 impl Default for Config {
   fn default() -> Self {
     Config {
-      witness_dir: PathBuf::from(r"C:\Program Files\Steam\steamapps\common\The Witness\"),
+      witness_dir: default_witness_dir(),
       logs_dir: std::env::current_dir().unwrap().join("audio_logs"),
+      installs: Vec::new(),
+      subtitle_fallback: SubtitleFallback::default(),
     }
   }
 }
 
+#[cfg(windows)]
+fn default_witness_dir() -> PathBuf {
+  PathBuf::from(r"C:\Program Files\Steam\steamapps\common\The Witness\")
+}
+
+#[cfg(not(windows))]
+fn default_witness_dir() -> PathBuf {
+  let home = std::env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+
+  [".local", "share", "Steam", "steamapps", "common", "The Witness"].iter()
+    .fold(home, |path, component| path.join(component))
+}
+
 impl Config {
   pub fn get() -> Self {
     if let Ok(raw) = fs::read_to_string("config.json") {
-      if let Ok(config) = serde_json::from_str(&raw) {
-        config
-      } else {
-          Config::default()
-      }
+      serde_json::from_str(&raw).unwrap_or_else(|_| Config::detect())
     } else {
-      Config::default()
+      Config::detect()
     }
   }
 
+  // A new config with every install that can be found registered, and the first of them selected
+  pub fn detect() -> Self {
+    let mut config = Config::default();
+
+    config.register_detected(discovery::find_installs());
+    if let Some(install) = config.installs.first() {
+      config.witness_dir = install.path.clone();
+    }
+
+    config
+  }
+
   pub fn save(&self) -> Result<()> {
     let json = serde_json::to_string_pretty(self)?;
     std::fs::write("config.json", json)?;
     Ok(())
   }
+
+  // -------------------------------------------------------------------------------------------------
+  // Install registry
+
+  pub fn find_install(&self, name: &str) -> Option<&Install> {
+    self.installs.iter().find(|install| install.name == name)
+  }
+
+  pub fn install_dir(&self, name: &str) -> Result<PathBuf> {
+    match self.find_install(name) {
+      Some(install) => Ok(install.path.clone()),
+      None => {
+        let known: Vec<&str> = self.installs.iter().map(|install| install.name.as_str()).collect();
+        Err(anyhow!("No install named `{}` (registered installs: {:?})", name, known))
+      },
+    }
+  }
+
+  pub fn add_install(&mut self, name: &str, path: &Path, source: InstallSource) -> Result<()> {
+    if self.find_install(name).is_some() {
+      return Err(anyhow!("An install named `{}` is already registered", name));
+    }
+    if let Some(existing) = self.installs.iter().find(|install| install.path == path) {
+      return Err(anyhow!("{:?} is already registered as `{}`", path, existing.name));
+    }
+    if !witness_data::witness_dir_is_okay(path) {
+      return Err(anyhow!("Could not find The Witness data files in {:?}", path));
+    }
+
+    self.installs.push(Install { name: name.to_owned(), path: path.to_owned(), source });
+    Ok(())
+  }
+
+  pub fn remove_install(&mut self, name: &str) -> Result<Install> {
+    let index = self.installs.iter()
+      .position(|install| install.name == name)
+      .ok_or_else(|| anyhow!("No install named `{}`", name))?;
+
+    Ok(self.installs.remove(index))
+  }
+
+  // Registers every detected install that isn't registered yet, named after the store it was found
+  // through. Returns the names of the newly registered installs.
+  pub fn register_detected(&mut self, detected: Vec<DetectedInstall>) -> Vec<String> {
+    let mut added = Vec::new();
+
+    for DetectedInstall { source, path } in detected {
+      if self.installs.iter().any(|install| install.path == path) {
+        continue;
+      }
+
+      let base_name = source.to_string();
+      let name = (1..)
+        .map(|n| if n == 1 { base_name.clone() } else { format!("{}-{}", base_name, n) })
+        .find(|name| self.find_install(name).is_none())
+        .unwrap();
+
+      self.installs.push(Install { name: name.clone(), path, source });
+      added.push(name);
+    }
+
+    added
+  }
 }
@@ -0,0 +1,132 @@
+use anyhow::{anyhow, Result};
+use serde::{Serialize, Deserialize};
+use std::{
+  collections::{BTreeMap, HashSet},
+  fs,
+  path::Path,
+};
+
+use crate::core::{
+  sources::log_key,
+  witness_data::AudioLog,
+};
+
+// ---------------------------------------------------------------------------------------------------
+// Placement constraints
+//
+// Slots are identified by their subtitle key (e.g. `schweickart_eva` for the mountaintop log), and
+// source logs by their path relative to the logs directory, with or without the audio extension
+// (e.g. `the_bouncer` or `feynman/wine.ogg`, see `sources.rs`).
+//
+//   {
+//     "pin":           { "schweickart_eva": "the_bouncer" },
+//     "keep_original": ["feynman_wine", "tagore_end"],
+//     "exclude_logs":  ["soulja_boy_braid.ogg"]
+//   }
+//
+// `pin` always places a source log in a slot, `keep_original` leaves slots untouched (`exclude_slots`
+// is accepted as another name for it), and `exclude_logs` keeps source logs from being placed.
+
+pub const CONSTRAINTS_FILE: &str = "constraints.json";
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Constraints {
+  pub pin: BTreeMap<String, String>,
+  #[serde(alias = "exclude_slots")]
+  pub keep_original: Vec<String>,
+  pub exclude_logs: Vec<String>,
+}
+
+impl Constraints {
+  pub fn load(path: &Path) -> Result<Self> {
+    let raw = fs::read_to_string(path)
+      .map_err(|err| anyhow!("Could not read constraints file {:?}: {}", path, err))?;
+
+    serde_json::from_str(&raw)
+      .map_err(|err| anyhow!("Could not parse constraints file {:?}: {}", path, err))
+  }
+
+  // Loads the `constraints.json` file in a logs directory, if there is one
+  pub fn for_logs_dir(logs_dir: &Path) -> Result<Self> {
+    let path = logs_dir.join(CONSTRAINTS_FILE);
+
+    if path.exists() {
+      Constraints::load(&path)
+    } else {
+      Ok(Constraints::default())
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.pin.is_empty() && self.keep_original.is_empty() && self.exclude_logs.is_empty()
+  }
+
+  pub fn is_pinned(&self, log_name: &str) -> bool {
+    self.pin.values().any(|pinned| log_key(pinned) == log_name)
+  }
+
+  pub fn is_excluded(&self, log_name: &str) -> bool {
+    self.exclude_logs.iter().any(|excluded| log_key(excluded) == log_name)
+  }
+
+  pub fn keeps_original(&self, slot: &AudioLog) -> bool {
+    self.keep_original.iter().any(|key| slot_matches(slot, key))
+  }
+
+  pub fn pinned_log(&self, slot: &AudioLog) -> Option<String> {
+    self.pin.iter()
+      .find(|(key, _)| slot_matches(slot, key))
+      .map(|(_, log)| log_key(log))
+  }
+
+  // Checks the constraints against the available slots and source logs, reporting every problem at
+  // once rather than just the first
+  pub fn validate(&self, slots: &[AudioLog], log_names: &[String]) -> Result<()> {
+    let mut problems: Vec<String> = Vec::new();
+
+    let known_slot = |key: &str| slots.iter().any(|slot| slot_matches(slot, key));
+    let known_log = |name: &str| log_names.iter().any(|known| *known == log_key(name));
+
+    let mut pinned_logs: HashSet<String> = HashSet::new();
+    for (slot, log) in &self.pin {
+      if !known_slot(slot) {
+        problems.push(format!("`{}` is pinned to unknown slot `{}`", log, slot));
+      }
+      if !known_log(log) {
+        problems.push(format!("Pinned log `{}` was not found in the logs directory", log));
+      }
+      if slots.iter().any(|known| slot_matches(known, slot) && self.keeps_original(known)) {
+        problems.push(format!("Slot `{}` is both pinned and kept original", slot));
+      }
+      if self.is_excluded(&log_key(log)) {
+        problems.push(format!("Log `{}` is both pinned and excluded", log));
+      }
+      if !pinned_logs.insert(log_key(log)) {
+        problems.push(format!("Log `{}` is pinned to more than one slot", log));
+      }
+    }
+
+    for slot in &self.keep_original {
+      if !known_slot(slot) {
+        problems.push(format!("Unknown slot `{}` in keep_original", slot));
+      }
+    }
+
+    for log in &self.exclude_logs {
+      if !known_log(log) {
+        log::warn!("Excluded log `{}` was not found in the logs directory", log);
+      }
+    }
+
+    if problems.is_empty() {
+      Ok(())
+    } else {
+      Err(anyhow!("Constraints cannot be satisfied:\n  {}", problems.join("\n  ")))
+    }
+  }
+}
+
+fn slot_matches(slot: &AudioLog, key: &str) -> bool {
+  slot.subtitle == key || slot.filename.to_string_lossy() == key
+}
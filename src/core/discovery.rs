@@ -0,0 +1,541 @@
+use anyhow::{anyhow, Result};
+use serde::{Serialize, Deserialize};
+use std::{
+  collections::HashMap,
+  fmt,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use crate::core::witness_data;
+
+// ---------------------------------------------------------------------------------------------------
+
+pub const WITNESS_STEAM_APP_ID: &str = "210970";
+pub const WITNESS_TITLE: &str = "The Witness";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallSource {
+  Steam,
+  Epic,
+  Gog,
+  Manual,
+}
+
+impl fmt::Display for InstallSource {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let name = match self {
+      InstallSource::Steam  => "steam",
+      InstallSource::Epic   => "epic",
+      InstallSource::Gog    => "gog",
+      InstallSource::Manual => "manual",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedInstall {
+  pub source: InstallSource,
+  pub path: PathBuf,
+}
+
+// Returns every install of The Witness that can be found in the Steam, Epic and GOG metadata
+pub fn find_installs() -> Vec<DetectedInstall> {
+  let steam = find_steam_installs().into_iter()
+    .map(|path| DetectedInstall { source: InstallSource::Steam, path });
+  let epic = find_epic_installs().into_iter()
+    .map(|path| DetectedInstall { source: InstallSource::Epic, path });
+  let gog = find_gog_installs().into_iter()
+    .map(|path| DetectedInstall { source: InstallSource::Gog, path });
+
+  let mut installs: Vec<DetectedInstall> = Vec::new();
+  for install in steam.chain(epic).chain(gog) {
+    if !installs.iter().any(|known| same_dir(&known.path, &install.path)) {
+      installs.push(install);
+    }
+  }
+
+  installs
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+  let a = a.canonicalize().unwrap_or_else(|_| a.to_owned());
+  let b = b.canonicalize().unwrap_or_else(|_| b.to_owned());
+  a == b
+}
+
+// ---------------------------------------------------------------------------------------------------
+// Steam
+
+// Returns the install directory of every Steam library containing The Witness
+pub fn find_steam_installs() -> Vec<PathBuf> {
+  let mut installs: Vec<PathBuf> = Vec::new();
+
+  for steam_root in steam_roots() {
+    for library in steam_library_folders(&steam_root) {
+      let Some(install) = witness_install_in_library(&library) else { continue };
+
+      if !installs.iter().any(|known| same_dir(known, &install)) {
+        installs.push(install);
+      }
+    }
+  }
+
+  installs
+}
+
+// Locations Steam is installed to by default. Only the ones that exist are returned.
+pub fn steam_roots() -> Vec<PathBuf> {
+  default_steam_roots().into_iter()
+    .filter(|path| path.is_dir())
+    .collect()
+}
+
+#[cfg(windows)]
+fn default_steam_roots() -> Vec<PathBuf> {
+  let mut roots = Vec::new();
+
+  for var in ["ProgramFiles(x86)", "ProgramFiles"] {
+    if let Some(program_files) = std::env::var_os(var) {
+      roots.push(PathBuf::from(program_files).join("Steam"));
+    }
+  }
+  roots.push(PathBuf::from(r"C:\Program Files (x86)\Steam"));
+  roots.push(PathBuf::from(r"C:\Program Files\Steam"));
+
+  roots
+}
+
+#[cfg(not(windows))]
+fn default_steam_roots() -> Vec<PathBuf> {
+  let Some(home) = std::env::var_os("HOME").map(PathBuf::from) else { return Vec::new() };
+
+  vec![
+    home.join(".local").join("share").join("Steam"),
+    home.join(".steam").join("steam"),
+    home.join(".steam").join("root"),
+    home.join(".var").join("app").join("com.valvesoftware.Steam").join(".local").join("share").join("Steam"),
+  ]
+}
+
+// Every library folder registered with the Steam install at `steam_root`, including the root itself
+pub fn steam_library_folders(steam_root: &Path) -> Vec<PathBuf> {
+  let mut folders = vec![steam_root.to_owned()];
+
+  let vdf_paths = [
+    steam_root.join("steamapps").join("libraryfolders.vdf"),
+    steam_root.join("config").join("libraryfolders.vdf"),
+  ];
+
+  for vdf_path in vdf_paths.iter().filter(|path| path.is_file()) {
+    let parsed = fs::read_to_string(vdf_path)
+      .map_err(anyhow::Error::from)
+      .and_then(|raw| parse_library_folders(&raw));
+
+    match parsed {
+      Ok(libraries) => {
+        for library in libraries {
+          if !folders.contains(&library.path) {
+            folders.push(library.path);
+          }
+        }
+      },
+      Err(err) => log::error!("Could not read Steam library folders from {:?}: {:?}", vdf_path, err),
+    }
+  }
+
+  folders
+}
+
+fn witness_install_in_library(library: &Path) -> Option<PathBuf> {
+  let steamapps = library.join("steamapps");
+  let manifest_path = steamapps.join(format!("appmanifest_{}.acf", WITNESS_STEAM_APP_ID));
+
+  let raw = fs::read_to_string(&manifest_path).ok()?;
+  let manifest = match parse_app_manifest(&raw) {
+    Ok(manifest) => manifest,
+    Err(err) => {
+      log::error!("Could not parse Steam app manifest {:?}: {:?}", manifest_path, err);
+      return None;
+    }
+  };
+
+  let install = steamapps.join("common").join(&manifest.install_dir);
+  if witness_data::witness_dir_is_okay(&install) {
+    Some(install)
+  } else {
+    None
+  }
+}
+
+// ---------------------------------------------------------------------------------------------------
+// Epic
+
+// Returns every install of The Witness listed in the Epic Games Launcher's `.item` manifests, or in
+// the installed games list of Legendary/Heroic on Linux
+pub fn find_epic_installs() -> Vec<PathBuf> {
+  let mut installs = Vec::new();
+
+  for manifests_dir in epic_manifest_dirs() {
+    let Ok(entries) = fs::read_dir(&manifests_dir) else { continue };
+
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+      if path.extension().and_then(|ext| ext.to_str()) != Some("item") { continue; }
+
+      let parsed = fs::read_to_string(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|raw| parse_epic_item(&raw));
+
+      match parsed {
+        Ok(item) if item.display_name == WITNESS_TITLE => installs.push(item.install_location),
+        Ok(_) => {},
+        Err(err) => log::error!("Could not parse Epic manifest {:?}: {:?}", path, err),
+      }
+    }
+  }
+
+  for installed_json in legendary_installed_files() {
+    let Ok(raw) = fs::read_to_string(&installed_json) else { continue };
+
+    match parse_legendary_installed(&raw) {
+      Ok(games) => installs.extend(
+        games.into_iter()
+          .filter(|game| game.title == WITNESS_TITLE)
+          .map(|game| game.install_path)
+      ),
+      Err(err) => log::error!("Could not parse Legendary install list {:?}: {:?}", installed_json, err),
+    }
+  }
+
+  installs.into_iter()
+    .filter(|path| witness_data::witness_dir_is_okay(path))
+    .collect()
+}
+
+#[cfg(windows)]
+fn epic_manifest_dirs() -> Vec<PathBuf> {
+  let program_data = std::env::var_os("ProgramData")
+    .map(PathBuf::from)
+    .unwrap_or_else(|| PathBuf::from(r"C:\ProgramData"));
+
+  vec![program_data.join("Epic").join("EpicGamesLauncher").join("Data").join("Manifests")]
+}
+
+#[cfg(not(windows))]
+fn epic_manifest_dirs() -> Vec<PathBuf> {
+  Vec::new()
+}
+
+fn legendary_installed_files() -> Vec<PathBuf> {
+  let Some(home) = std::env::var_os("HOME").map(PathBuf::from) else { return Vec::new() };
+  let config = home.join(".config");
+
+  vec![
+    config.join("legendary").join("installed.json"),
+    config.join("heroic").join("legendaryConfig").join("legendary").join("installed.json"),
+  ]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct EpicItem {
+  #[serde(rename = "DisplayName")]
+  pub display_name: String,
+  #[serde(rename = "InstallLocation")]
+  pub install_location: PathBuf,
+  #[serde(rename = "AppName", default)]
+  pub app_name: String,
+}
+
+pub fn parse_epic_item(raw: &str) -> Result<EpicItem> {
+  serde_json::from_str(raw.trim_start_matches('\u{feff}')).map_err(anyhow::Error::from)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct LegendaryGame {
+  pub title: String,
+  pub install_path: PathBuf,
+}
+
+pub fn parse_legendary_installed(raw: &str) -> Result<Vec<LegendaryGame>> {
+  let games: HashMap<String, LegendaryGame> = serde_json::from_str(raw)?;
+  Ok(games.into_values().collect())
+}
+
+// ---------------------------------------------------------------------------------------------------
+// GOG
+
+// GOG installs are identified by the `goggame-<id>.info` file GOG Galaxy and the offline installers
+// place in the game directory. Installs are looked for in the default library locations and in
+// Heroic's list of installed GOG games.
+pub fn find_gog_installs() -> Vec<PathBuf> {
+  let mut candidates: Vec<PathBuf> = Vec::new();
+
+  for library in gog_library_dirs() {
+    let Ok(entries) = fs::read_dir(&library) else { continue };
+    candidates.extend(
+      entries.filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+    );
+  }
+
+  for installed_json in heroic_gog_installed_files() {
+    let Ok(raw) = fs::read_to_string(&installed_json) else { continue };
+
+    match parse_heroic_gog_installed(&raw) {
+      Ok(paths) => candidates.extend(paths),
+      Err(err) => log::error!("Could not parse Heroic GOG install list {:?}: {:?}", installed_json, err),
+    }
+  }
+
+  candidates.into_iter()
+    .filter(|dir| gog_game_info(dir).map(|info| info.name == WITNESS_TITLE).unwrap_or(false))
+    .filter(|dir| witness_data::witness_dir_is_okay(dir))
+    .collect()
+}
+
+#[cfg(windows)]
+fn gog_library_dirs() -> Vec<PathBuf> {
+  vec![
+    PathBuf::from(r"C:\Program Files (x86)\GOG Galaxy\Games"),
+    PathBuf::from(r"C:\GOG Games"),
+  ]
+}
+
+#[cfg(not(windows))]
+fn gog_library_dirs() -> Vec<PathBuf> {
+  let Some(home) = std::env::var_os("HOME").map(PathBuf::from) else { return Vec::new() };
+
+  vec![
+    home.join("GOG Games"),
+    home.join("Games").join("Heroic"),
+  ]
+}
+
+fn heroic_gog_installed_files() -> Vec<PathBuf> {
+  let Some(home) = std::env::var_os("HOME").map(PathBuf::from) else { return Vec::new() };
+
+  vec![home.join(".config").join("heroic").join("gog_store").join("installed.json")]
+}
+
+fn gog_game_info(dir: &Path) -> Option<GogGameInfo> {
+  fs::read_dir(dir).ok()?
+    .filter_map(|entry| entry.ok())
+    .map(|entry| entry.path())
+    .filter(|path| {
+      let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+      name.starts_with("goggame-") && name.ends_with(".info")
+    })
+    .find_map(|path| {
+      let raw = fs::read_to_string(&path).ok()?;
+      parse_gog_game_info(&raw).ok()
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct GogGameInfo {
+  #[serde(rename = "gameId")]
+  pub game_id: String,
+  pub name: String,
+}
+
+pub fn parse_gog_game_info(raw: &str) -> Result<GogGameInfo> {
+  serde_json::from_str(raw.trim_start_matches('\u{feff}')).map_err(anyhow::Error::from)
+}
+
+#[derive(Debug, Deserialize)]
+struct HeroicGogInstalled {
+  installed: Vec<HeroicGogGame>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HeroicGogGame {
+  install_path: PathBuf,
+}
+
+pub fn parse_heroic_gog_installed(raw: &str) -> Result<Vec<PathBuf>> {
+  let installed: HeroicGogInstalled = serde_json::from_str(raw)?;
+  Ok(installed.installed.into_iter().map(|game| game.install_path).collect())
+}
+
+// ---------------------------------------------------------------------------------------------------
+// Steam data files
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryFolder {
+  pub path: PathBuf,
+  pub apps: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppManifest {
+  pub app_id: String,
+  pub name: String,
+  pub install_dir: String,
+}
+
+// Handles both the current format, in which each library is an object with a "path" key and an
+// "apps" list, and the older format in which each library is just a numbered path
+pub fn parse_library_folders(raw: &str) -> Result<Vec<LibraryFolder>> {
+  let root = parse_vdf(raw)?;
+  let folders = root.get("libraryfolders")
+    .or_else(|| root.get("LibraryFolders"))
+    .ok_or_else(|| anyhow!("Missing `libraryfolders` section"))?;
+
+  let VdfValue::Object(entries) = folders else {
+    return Err(anyhow!("`libraryfolders` is not a section"));
+  };
+
+  let libraries = entries.iter()
+    .filter(|(key, _)| key.chars().all(|c| c.is_ascii_digit()))
+    .filter_map(|(_, value)| match value {
+      VdfValue::String(path) => Some(LibraryFolder { path: PathBuf::from(path), apps: Vec::new() }),
+      VdfValue::Object(_) => {
+        let path = value.get("path")?.as_str()?;
+        let apps = match value.get("apps") {
+          Some(VdfValue::Object(apps)) => apps.iter().map(|(app_id, _)| app_id.clone()).collect(),
+          _ => Vec::new(),
+        };
+        Some(LibraryFolder { path: PathBuf::from(path), apps })
+      },
+    })
+    .collect();
+
+  Ok(libraries)
+}
+
+pub fn parse_app_manifest(raw: &str) -> Result<AppManifest> {
+  let root = parse_vdf(raw)?;
+  let state = root.get("AppState")
+    .ok_or_else(|| anyhow!("Missing `AppState` section"))?;
+
+  let field = |key: &str| {
+    state.get(key)
+      .and_then(VdfValue::as_str)
+      .map(str::to_owned)
+      .ok_or_else(|| anyhow!("Missing `{}` field", key))
+  };
+
+  Ok(AppManifest {
+    app_id:      field("appid")?,
+    name:        field("name").unwrap_or_default(),
+    install_dir: field("installdir")?,
+  })
+}
+
+// ---------------------------------------------------------------------------------------------------
+// Valve KeyValues (.vdf/.acf) text format
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VdfValue {
+  String(String),
+  Object(Vec<(String, VdfValue)>),
+}
+
+impl VdfValue {
+  // Keys are case-insensitive
+  pub fn get(&self, key: &str) -> Option<&VdfValue> {
+    match self {
+      VdfValue::Object(entries) => entries.iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v),
+      VdfValue::String(_) => None,
+    }
+  }
+
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      VdfValue::String(s) => Some(s),
+      VdfValue::Object(_) => None,
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+enum VdfToken {
+  Str(String),
+  Open,
+  Close,
+}
+
+pub fn parse_vdf(raw: &str) -> Result<VdfValue> {
+  let tokens = tokenize_vdf(raw)?;
+  let mut iter = tokens.into_iter().peekable();
+
+  let entries = parse_vdf_entries(&mut iter, false)?;
+  Ok(VdfValue::Object(entries))
+}
+
+fn parse_vdf_entries<I: Iterator<Item = VdfToken>>(
+  tokens: &mut std::iter::Peekable<I>,
+  nested: bool,
+) -> Result<Vec<(String, VdfValue)>> {
+  let mut entries = Vec::new();
+
+  loop {
+    let key = match tokens.next() {
+      Some(VdfToken::Str(key)) => key,
+      Some(VdfToken::Close) if nested => return Ok(entries),
+      Some(VdfToken::Close) => return Err(anyhow!("Unexpected `}}`")),
+      Some(VdfToken::Open) => return Err(anyhow!("Unexpected `{{`")),
+      None if nested => return Err(anyhow!("Unexpected end of file, expected `}}`")),
+      None => return Ok(entries),
+    };
+
+    let value = match tokens.next() {
+      Some(VdfToken::Str(value)) => VdfValue::String(value),
+      Some(VdfToken::Open) => VdfValue::Object(parse_vdf_entries(tokens, true)?),
+      _ => return Err(anyhow!("Missing value for key `{}`", key)),
+    };
+
+    entries.push((key, value));
+  }
+}
+
+fn tokenize_vdf(raw: &str) -> Result<Vec<VdfToken>> {
+  let mut tokens = Vec::new();
+  let mut chars = raw.trim_start_matches('\u{feff}').chars().peekable();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '{' => tokens.push(VdfToken::Open),
+      '}' => tokens.push(VdfToken::Close),
+      '"' => {
+        let mut s = String::new();
+        loop {
+          match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+              Some('n') => s.push('\n'),
+              Some('t') => s.push('\t'),
+              Some(escaped) => s.push(escaped),
+              None => return Err(anyhow!("Unterminated string")),
+            },
+            Some(other) => s.push(other),
+            None => return Err(anyhow!("Unterminated string")),
+          }
+        }
+        tokens.push(VdfToken::Str(s));
+      },
+      '/' if chars.peek() == Some(&'/') => {
+        for skipped in chars.by_ref() {
+          if skipped == '\n' { break; }
+        }
+      },
+      c if c.is_whitespace() => {},
+      c => {
+        let mut s = String::from(c);
+        while let Some(&next) = chars.peek() {
+          if next.is_whitespace() || next == '{' || next == '}' || next == '"' { break; }
+          s.push(next);
+          chars.next();
+        }
+        tokens.push(VdfToken::Str(s));
+      },
+    }
+  }
+
+  Ok(tokens)
+}
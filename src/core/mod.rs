@@ -1,5 +1,15 @@
-pub(crate) mod config;
-pub(crate) mod randomizer;
-pub(crate) mod util;
-pub(crate) mod witness_data;
-pub(crate) mod zip;
+pub mod config;
+pub mod constraints;
+pub mod discovery;
+pub mod pack;
+pub mod plando;
+pub mod randomizer;
+pub mod seed;
+pub mod settings;
+pub mod sources;
+pub mod spoiler;
+pub mod subtitles;
+pub mod util;
+pub mod weights;
+pub mod witness_data;
+pub mod zip;
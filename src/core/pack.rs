@@ -0,0 +1,277 @@
+use anyhow::{anyhow, Result};
+use serde::{Serialize, Deserialize};
+use std::{
+  fs,
+  io,
+  io::Read,
+  path::{Component, Path, PathBuf},
+  sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::core::{
+  seed,
+  subtitles,
+  weights,
+  weights::Weight,
+};
+
+// ---------------------------------------------------------------------------------------------------
+// Audio log packs
+//
+// A pack is a zip file containing audio logs, their subtitles, and a `pack.json` manifest at the root
+// of the archive listing every log in the pack:
+//
+//   {
+//     "name": "Lectures on physics",
+//     "author": "Someone",
+//     "logs": [
+//       {
+//         "file": "wine.ogg",
+//         "subtitles": "wine.sub",
+//         "title": "A glass of wine",
+//         "speaker": "Richard Feynman",
+//         "tags": ["physics"],
+//         "weight": 2
+//       }
+//     ]
+//   }
+//
+// Only `file` is required. `subtitles` defaults to the `.sub` file (or transcript, see
+// `subtitles::SUBTITLE_EXTENSIONS`) next to the log if there is one, and `author` defaults to the
+// author of the pack. Files in the archive that are not listed in the manifest are ignored.
+//
+// Packs are extracted to a cache in the temp directory the first time they are used, so the
+// randomizer can read them like loose files. Each pack has a folder in the cache, keyed on the path
+// of the zip file, holding one version of its contents keyed on the size and modification time of
+// the zip file and the hash of its manifest. Older versions are removed when a new one is extracted.
+
+pub const PACK_MANIFEST: &str = "pack.json";
+pub const PACK_EXTENSION: &str = "zip";
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogInfo {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub speaker: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub author: Option<String>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackManifest {
+  #[serde(default)]
+  pub name: Option<String>,
+  #[serde(default)]
+  pub author: Option<String>,
+  pub logs: Vec<PackEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackEntry {
+  pub file: String,
+  #[serde(default)]
+  pub subtitles: Option<String>,
+  #[serde(default)]
+  pub weight: Option<serde_json::Value>,
+  #[serde(flatten)]
+  pub info: LogInfo,
+}
+
+// One log from an extracted pack
+#[derive(Debug, Clone)]
+pub struct PackLog {
+  pub audio: PathBuf,
+  pub subs: Option<PathBuf>,
+  pub weight: Option<Weight>,
+  pub info: LogInfo,
+
+  // The path of the log inside the archive
+  pub file: PathBuf,
+}
+
+pub fn is_pack_file(path: &Path) -> bool {
+  path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case(PACK_EXTENSION))
+}
+
+pub fn parse_manifest(raw: &str) -> Result<PackManifest> {
+  serde_json::from_str(raw).map_err(|err| anyhow!("Invalid {}: {}", PACK_MANIFEST, err))
+}
+
+// Whether a zip file has a `pack.json` manifest, since zip files without one are not packs
+pub fn has_manifest(pack_path: &Path) -> Result<bool> {
+  let mut archive = open_archive(pack_path)?;
+  let has_manifest = archive.by_name(PACK_MANIFEST).is_ok();
+
+  Ok(has_manifest)
+}
+
+pub fn read_manifest(pack_path: &Path) -> Result<PackManifest> {
+  parse_manifest(&read_raw_manifest(pack_path)?).map_err(|err| anyhow!("{:?}: {}", pack_path, err))
+}
+
+fn read_raw_manifest(pack_path: &Path) -> Result<String> {
+  let mut archive = open_archive(pack_path)?;
+  let mut manifest = archive.by_name(PACK_MANIFEST)
+    .map_err(|_| anyhow!("{:?} is not an audio log pack: it has no {}", pack_path, PACK_MANIFEST))?;
+
+  let mut raw = String::new();
+  manifest.read_to_string(&mut raw)?;
+
+  Ok(raw)
+}
+
+// Extracts a pack to the cache if needed, and returns the logs listed in its manifest
+pub fn load_pack(pack_path: &Path) -> Result<Vec<PackLog>> {
+  let raw_manifest = read_raw_manifest(pack_path)?;
+  let manifest = parse_manifest(&raw_manifest).map_err(|err| anyhow!("{:?}: {}", pack_path, err))?;
+  let dir = extract_pack(pack_path, seed::fnv1a_64(raw_manifest.as_bytes()))?;
+
+  manifest.logs.into_iter()
+    .map(|entry| {
+      let file = PathBuf::from(&entry.file);
+      let in_pack = |path: &Path| path.components().all(|component| matches!(component, Component::Normal(_)));
+      if !in_pack(&file) || !entry.subtitles.as_deref().is_none_or(|subs| in_pack(Path::new(subs))) {
+        return Err(anyhow!("{:?}: `{}` is not a path inside the pack", pack_path, entry.file));
+      }
+
+      let audio = dir.join(&file);
+      if !audio.is_file() {
+        return Err(anyhow!("{:?}: `{}` is listed in {} but is not in the pack", pack_path, entry.file, PACK_MANIFEST));
+      }
+
+      let subs = match &entry.subtitles {
+        Some(subtitles) => {
+          let subs = dir.join(subtitles);
+          if !subs.is_file() {
+            return Err(anyhow!("{:?}: subtitles file `{}` is not in the pack", pack_path, subtitles));
+          }
+          Some(subs)
+        },
+        None => subtitles::find_subs_file(&audio, None),
+      };
+
+      let weight = entry.weight.as_ref()
+        .map(weights::weight_from_json)
+        .transpose()
+        .map_err(|err| anyhow!("{:?}, log `{}`: {}", pack_path, entry.file, err))?;
+
+      let mut info = entry.info;
+      if info.author.is_none() {
+        info.author = manifest.author.clone();
+      }
+
+      Ok(PackLog { audio, subs, weight, info, file })
+    })
+    .collect()
+}
+
+fn open_archive(pack_path: &Path) -> Result<::zip::ZipArchive<fs::File>> {
+  let file = fs::File::open(pack_path)
+    .map_err(|err| anyhow!("Could not open pack {:?}: {}", pack_path, err))?;
+
+  ::zip::ZipArchive::new(file).map_err(|err| anyhow!("Could not read pack {:?}: {}", pack_path, err))
+}
+
+// The folder of a pack in the cache, and the folder of the current version of its contents in it
+fn cache_dirs(pack_path: &Path, manifest_hash: u64) -> Result<(PathBuf, PathBuf)> {
+  let metadata = fs::metadata(pack_path)?;
+  let modified = metadata.modified()?
+    .duration_since(std::time::UNIX_EPOCH)
+    .map(|duration| duration.as_nanos())
+    .unwrap_or_default();
+
+  let path = fs::canonicalize(pack_path)?;
+  let stem = pack_path.file_stem().unwrap_or_default().to_string_lossy();
+  let pack_dir = std::env::temp_dir()
+    .join("witness_audio_randomizer")
+    .join("packs")
+    .join(format!("{}-{:016x}", stem, seed::fnv1a_64(path.to_string_lossy().as_bytes())));
+
+  let version = format!("{}|{}|{:016x}", metadata.len(), modified, manifest_hash);
+  let version_dir = pack_dir.join(format!("{:016x}", seed::fnv1a_64(version.as_bytes())));
+
+  Ok((pack_dir, version_dir))
+}
+
+// Whether a cached version of a pack was extracted from a pack with this manifest
+fn has_manifest_hash(dir: &Path, manifest_hash: u64) -> bool {
+  fs::read(dir.join(PACK_MANIFEST)).is_ok_and(|raw| seed::fnv1a_64(&raw) == manifest_hash)
+}
+
+// Removes the other versions of a pack from the cache. Unfinished extractions are left alone unless
+// they are old enough that the run that started them must be gone.
+fn evict_stale_versions(pack_dir: &Path, current: &Path) -> Result<()> {
+  let max_partial_age = std::time::Duration::from_secs(60 * 60);
+
+  for entry in fs::read_dir(pack_dir)? {
+    let path = entry?.path();
+    if path == current {
+      continue;
+    }
+
+    let is_partial = path.extension().is_some_and(|ext| ext.to_string_lossy().starts_with("partial"));
+    let age = fs::metadata(&path)?.modified()?.elapsed().unwrap_or_default();
+    if !is_partial || age > max_partial_age {
+      fs::remove_dir_all(&path)?;
+    }
+  }
+
+  Ok(())
+}
+
+fn extract_pack(pack_path: &Path, manifest_hash: u64) -> Result<PathBuf> {
+  let (pack_dir, dir) = cache_dirs(pack_path, manifest_hash)?;
+  if dir.is_dir() {
+    return Ok(dir);
+  }
+
+  fs::create_dir_all(&pack_dir)?;
+  if let Err(err) = evict_stale_versions(&pack_dir, &dir) {
+    log::warn!("Could not remove old versions of {:?} from the cache: {:?}", pack_path, err);
+  }
+
+  // Extract next to the final location and rename, so that an interrupted extraction is never
+  // mistaken for a complete one. Every extraction has its own folder, since another run may be
+  // extracting the same pack at the same time.
+  static EXTRACTIONS: AtomicUsize = AtomicUsize::new(0);
+  let extraction = EXTRACTIONS.fetch_add(1, Ordering::Relaxed);
+  let partial = dir.with_extension(format!("partial-{}-{}", std::process::id(), extraction));
+  if partial.exists() {
+    fs::remove_dir_all(&partial)?;
+  }
+
+  let mut archive = open_archive(pack_path)?;
+  for i in 0..archive.len() {
+    let mut file = archive.by_index(i)?;
+    let outpath = match file.enclosed_name() {
+      Some(name) => partial.join(name),
+      None => continue,
+    };
+
+    if file.is_dir() {
+      fs::create_dir_all(&outpath)?;
+    } else {
+      if let Some(parent) = outpath.parent() {
+        fs::create_dir_all(parent)?;
+      }
+      let mut outfile = fs::File::create(&outpath)?;
+      io::copy(&mut file, &mut outfile)?;
+    }
+  }
+
+  fs::create_dir_all(&partial)?;
+  if let Err(err) = fs::rename(&partial, &dir) {
+    let _ = fs::remove_dir_all(&partial);
+
+    // Another run finished extracting the same version first
+    if !has_manifest_hash(&dir, manifest_hash) {
+      return Err(anyhow!("Could not extract pack {:?} to {:?}: {}", pack_path, dir, err));
+    }
+  }
+
+  Ok(dir)
+}
@@ -0,0 +1,126 @@
+use anyhow::{anyhow, Result};
+use serde::{Serialize, Deserialize};
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use crate::core::{
+  randomizer::Placement,
+  subtitles,
+  weights::Weight,
+  witness_data::{AudioLog, DataStore},
+};
+
+// ---------------------------------------------------------------------------------------------------
+// Plando files
+//
+// A plando file places logs by hand instead of at random. It maps slot subtitle keys (see
+// `list-slots`) to the log to put in that slot, given either as the path to an `.ogg` file, whose
+// subtitles file next to it is used if there is one, or as an object naming both files. Slots mapped
+// to `null`, and slots that are not listed, keep their original log. Relative paths are relative to
+// the plando file.
+//
+//   {
+//     "schweickart_eva": "logs/the_bouncer.ogg",
+//     "tagore_end":      { "audio": "logs/outro.ogg", "subtitles": "subs/outro.sub" },
+//     "feynman_wine":    null
+//   }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PlandoEntry {
+  Audio(PathBuf),
+  Files {
+    audio: PathBuf,
+    #[serde(default)]
+    subtitles: Option<PathBuf>,
+  },
+}
+
+#[derive(Debug, Clone)]
+pub struct Plando {
+  pub entries: BTreeMap<String, Option<PlandoEntry>>,
+
+  // The directory relative paths are resolved from
+  pub base_dir: PathBuf,
+}
+
+impl Plando {
+  pub fn load(path: &Path) -> Result<Self> {
+    let raw = fs::read_to_string(path)
+      .map_err(|err| anyhow!("Could not read plando file {:?}: {}", path, err))?;
+    let base_dir = path.parent().map(Path::to_owned).unwrap_or_default();
+
+    Plando::parse(&raw, &base_dir).map_err(|err| anyhow!("Plando file {:?}: {}", path, err))
+  }
+
+  pub fn parse(raw: &str, base_dir: &Path) -> Result<Self> {
+    let entries = serde_json::from_str(raw)?;
+
+    Ok(Plando { entries, base_dir: base_dir.to_owned() })
+  }
+
+  // Checks the file against the game's slots and the files on disk, reporting every problem at
+  // once, and returns the placements it describes
+  pub fn placements(&self) -> Result<Vec<Placement>> {
+    let slots = DataStore::get_logs()?;
+    let mut problems: Vec<String> = Vec::new();
+    let mut placements: Vec<Placement> = Vec::new();
+
+    for (key, entry) in &self.entries {
+      let slot = slots.iter().find(|slot| &slot.subtitle == key);
+      let Some(slot) = slot else {
+        problems.push(format!("Unknown slot `{}`", key));
+        continue;
+      };
+
+      let Some(entry) = entry else {
+        continue;
+      };
+
+      match self.placement(slot, entry) {
+        Ok(placement) => placements.push(placement),
+        Err(err) => problems.push(format!("`{}`: {}", key, err)),
+      }
+    }
+
+    if problems.is_empty() {
+      Ok(placements)
+    } else {
+      Err(anyhow!("Invalid plando file:\n  {}", problems.join("\n  ")))
+    }
+  }
+
+  fn placement(&self, slot: &AudioLog, entry: &PlandoEntry) -> Result<Placement> {
+    let (audio, subs) = match entry {
+      PlandoEntry::Audio(audio) => {
+        let audio = self.base_dir.join(audio);
+        let subs = subtitles::find_subs_file(&audio, None);
+        (audio, subs)
+      },
+      PlandoEntry::Files { audio, subtitles } => {
+        (self.base_dir.join(audio), subtitles.as_ref().map(|subs| self.base_dir.join(subs)))
+      },
+    };
+
+    if !audio.is_file() {
+      return Err(anyhow!("audio file {:?} does not exist", audio));
+    }
+    if let Some(subs) = &subs {
+      if !subs.is_file() {
+        return Err(anyhow!("subtitles file {:?} does not exist", subs));
+      }
+    }
+
+    Ok(Placement {
+      slot: slot.clone(),
+      audio,
+      subs,
+      weight: Weight::default(),
+      pack: None,
+      info: None,
+    })
+  }
+}
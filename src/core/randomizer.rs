@@ -1,91 +1,447 @@
-use rand::{
-  rngs::StdRng,
-  seq::SliceRandom, SeedableRng,
-};
+use anyhow::{anyhow, Result};
 use std::{
   collections::HashMap,
+  fmt,
   fs,
   path::{Path, PathBuf},
+  str::FromStr,
 };
 
-use crate::core::witness_data::{
-  AudioLog, DataStore, SoundDestination, SoundInsertion, SoundInsertionMap, SubsInsertionMap
+use crate::core::{
+  constraints::Constraints,
+  pack::LogInfo,
+  seed,
+  sources,
+  sources::LogFilter,
+  subtitles,
+  weights,
+  weights::Weight,
+  witness_data,
+  witness_data::{
+    AudioLog, DataStore, SoundDestination, SoundInsertion, SoundInsertionMap, SubsInsertionMap, SubtitleFallback
+  },
 };
 
-pub fn randomize(seed: u64, src_dir: &Path) -> (SoundInsertionMap, SubsInsertionMap) {
-  let mut logs_to_insert = get_entries(src_dir);
-  let mut logs_data = DataStore::get_logs();
+// A custom audio log placed into one of the game's audio log slots
+#[derive(Debug, Clone)]
+pub struct Placement {
+  pub slot:   AudioLog,
+  pub audio:  PathBuf,
+  pub subs:   Option<PathBuf>,
+  pub weight: Weight,
+  pub pack:   Option<String>,
+  pub info:   Option<LogInfo>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct RandomizerOptions {
+  pub constraints: Constraints,
+
+  // Which of the logs in the logs directory are used
+  pub filter: LogFilter,
+
+  // Reuse logs so that every slot is replaced when there are fewer logs than slots
+  pub fill: bool,
+
+  // The game's original logs, extracted with `witness_data::extract_original_logs`. When set, the
+  // slots not taken by custom logs are filled with shuffled originals.
+  pub originals_dir: Option<PathBuf>,
+
+  // How many of the slots get custom logs when originals are mixed in
+  pub custom_share: CustomShare,
+
+  pub areas: AreaRules,
+}
+
+// Rules on the areas of the island custom logs are placed in. Slots whose area is not known are not
+// counted by any of these rules, and are left out when only some areas are randomized.
+#[derive(Debug, Default, Clone)]
+pub struct AreaRules {
+  // Only randomize the slots in these areas
+  pub only: Option<Vec<String>>,
+
+  // The most custom logs placed in any one area
+  pub max_per_area: Option<usize>,
+
+  // Spread the logs of each pack across as many areas as possible
+  pub spread_packs: bool,
+}
+
+impl AreaRules {
+  pub fn is_empty(&self) -> bool {
+    self.only.is_none() && self.max_per_area.is_none() && !self.spread_packs
+  }
+
+  fn includes(&self, slot: &AudioLog) -> bool {
+    match (&self.only, &slot.area) {
+      (None, _) => true,
+      (Some(areas), Some(area)) => areas.iter().any(|only| only.eq_ignore_ascii_case(area)),
+      (Some(_), None) => false,
+    }
+  }
+
+  fn validate(&self, slots: &[AudioLog]) -> Result<()> {
+    let unknown: Vec<&str> = self.only.iter().flatten()
+      .filter(|only| !slots.iter().any(|slot| slot.area.as_ref().is_some_and(|area| area.eq_ignore_ascii_case(only))))
+      .map(String::as_str)
+      .collect();
+
+    if !unknown.is_empty() {
+      return Err(anyhow!(
+        "Unknown area(s): {}. Slots get their area from the `area` field of the slot data, which can \
+         be set in a `{}` file or `{}` folder in the config directory (see `list-slots`)",
+        unknown.join(", "),
+        witness_data::SLOTS_FILE,
+        witness_data::SLOTS_OVERLAY_DIR,
+      ));
+    }
+
+    if !self.is_empty() && !slots.iter().any(|slot| slot.area.is_some()) {
+      return Err(anyhow!(
+        "No slot has an area, so the area rules can't be applied. The built-in slot data has no areas \
+         yet; they can be set in a `{}` file or `{}` folder in the config directory (see `list-slots`)",
+        witness_data::SLOTS_FILE,
+        witness_data::SLOTS_OVERLAY_DIR,
+      ));
+    }
+
+    Ok(())
+  }
+
+  // Places each log in the first of the (shuffled) slots that the rules allow, and returns the
+  // placements along with the slots left over. Without any rules this places the logs in the slots
+  // in order. Logs that no slot is left for are not placed.
+  fn assign(&self, logs: Vec<NewLog>, slots: Vec<AudioLog>) -> (Vec<Placement>, Vec<AudioLog>) {
+    let mut slots: Vec<Option<AudioLog>> = slots.into_iter().map(Some).collect();
+    let mut per_area: HashMap<String, usize> = HashMap::new();
+    let mut per_pack_area: HashMap<(String, String), usize> = HashMap::new();
+    let mut placements: Vec<Placement> = Vec::new();
+
+    for log in logs {
+      let candidates = slots.iter().enumerate()
+        .filter_map(|(index, slot)| Some((index, slot.as_ref()?)))
+        .filter(|(_, slot)| match (&slot.area, self.max_per_area) {
+          (Some(area), Some(max)) => per_area.get(area).copied().unwrap_or(0) < max,
+          _ => true,
+        });
+
+      // Slots without an area come last, since placing a log there doesn't spread its pack
+      let chosen = match &log.pack {
+        Some(pack) if self.spread_packs => candidates
+          .min_by_key(|(_, slot)| match &slot.area {
+            Some(area) => (false, per_pack_area.get(&(pack.clone(), area.clone())).copied().unwrap_or(0)),
+            None => (true, 0),
+          })
+          .map(|(index, _)| index),
+        _ => candidates.map(|(index, _)| index).next(),
+      };
+
+      let Some(index) = chosen else {
+        log::warn!("No slot left for {:?} within the area rules", log.audio);
+        continue;
+      };
+
+      let slot = slots[index].take().unwrap();
+      if let Some(area) = &slot.area {
+        *per_area.entry(area.clone()).or_default() += 1;
+        if let Some(pack) = &log.pack {
+          *per_pack_area.entry((pack.clone(), area.clone())).or_default() += 1;
+        }
+      }
+
+      placements.push(log.place(slot));
+    }
+
+    (placements, slots.into_iter().flatten().collect())
+  }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum CustomShare {
+  #[default]
+  All,
+  Count(usize),
+  Ratio(f64),
+}
+
+impl CustomShare {
+  // The number of slots out of `slot_count` that get custom logs
+  pub fn count_of(&self, slot_count: usize) -> usize {
+    match self {
+      CustomShare::All => slot_count,
+      CustomShare::Count(count) => std::cmp::min(*count, slot_count),
+      CustomShare::Ratio(ratio) => std::cmp::min((ratio * slot_count as f64).round() as usize, slot_count),
+    }
+  }
+}
+
+impl fmt::Display for CustomShare {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      CustomShare::All => write!(f, "all"),
+      CustomShare::Count(count) => write!(f, "{}", count),
+      CustomShare::Ratio(ratio) => write!(f, "{}%", ratio * 100.0),
+    }
+  }
+}
+
+// Either `all`, a number of logs, or a percentage of the slots (e.g. `30%`)
+impl FromStr for CustomShare {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    let s = s.trim();
+
+    if s.eq_ignore_ascii_case("all") {
+      return Ok(CustomShare::All);
+    }
+
+    if let Some(percent) = s.strip_suffix('%') {
+      return match percent.trim().parse::<f64>() {
+        Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(CustomShare::Ratio(percent / 100.0)),
+        _ => Err(anyhow!("Invalid percentage `{}`, expected a number from 0 to 100", s)),
+      };
+    }
+
+    s.parse().map(CustomShare::Count).map_err(|_| anyhow!("Invalid custom log share `{}`", s))
+  }
+}
+
+pub fn randomize(seed: u64, src_dir: &Path, options: &RandomizerOptions) -> Result<(SoundInsertionMap, SubsInsertionMap)> {
+  Ok(insertion_maps(&place(seed, src_dir, options)?))
+}
+
+pub fn place(seed: u64, src_dir: &Path, options: &RandomizerOptions) -> Result<Vec<Placement>> {
+  place_in_slots(seed, DataStore::get_logs()?, src_dir, options)
+}
+
+// Like `place`, with the given slot data instead of the game's
+pub fn place_in_slots(seed: u64, logs_data: Vec<AudioLog>, src_dir: &Path, options: &RandomizerOptions) -> Result<Vec<Placement>> {
+  let constraints = &options.constraints;
+
+  let logs_to_insert = get_entries(src_dir, &options.filter)?;
+  let originals = match &options.originals_dir {
+    Some(originals_dir) => get_entries(originals_dir, &LogFilter::default())?,
+    None => Vec::new(),
+  };
+
+  let log_names: Vec<String> = logs_to_insert.iter().chain(&originals).map(|log| log.name().to_owned()).collect();
+  constraints.validate(&logs_data, &log_names)?;
+
+  // Pinned logs are placed first, and are left out of the random placement along with excluded
+  // logs and the slots that are kept original
+  let mut placements: Vec<Placement> = logs_data.iter()
+    .filter_map(|slot| {
+      let pinned = constraints.pinned_log(slot)?;
+      let log = logs_to_insert.iter().chain(&originals).find(|log| log.name() == pinned)?.clone();
+      Some(log.place(slot.clone()))
+    })
+    .collect();
+
+  let unconstrained = |log: &NewLog| !constraints.is_pinned(log.name()) && !constraints.is_excluded(log.name());
 
-  let insert_count = std::cmp::min( logs_to_insert.len(), logs_data.len() );
+  options.areas.validate(&logs_data)?;
 
-  let mut rng = StdRng::seed_from_u64(seed);
+  let (mut logs_data, _): (Vec<AudioLog>, Vec<AudioLog>) = logs_data.into_iter()
+    .partition(|slot| {
+      constraints.pinned_log(slot).is_none() && !constraints.keeps_original(slot) && options.areas.includes(slot)
+    });
+  let mut logs_to_insert: Vec<NewLog> = logs_to_insert.into_iter().filter(unconstrained).collect();
+  let mut originals: Vec<NewLog> = originals.into_iter().filter(unconstrained).collect();
 
-  logs_to_insert.shuffle(&mut rng);
-  let logs_to_insert_iter = logs_to_insert.into_iter().take(insert_count);
+  let slot_count = logs_data.len();
+  let custom_count = options.custom_share.count_of(slot_count);
 
-  logs_data.shuffle(&mut rng);
-  let logs_data_iter = logs_data.into_iter().take(insert_count);
+  let eligible_count = logs_to_insert.iter().filter(|log| !log.weight.is_never()).count();
+  let insert_count = std::cmp::min( eligible_count, custom_count );
 
-  let pairs = std::iter::zip(logs_data_iter, logs_to_insert_iter);
+  let mut rng = seed::rng_from_seed(seed);
+
+  seed::shuffle(&mut logs_to_insert, &mut rng);
+  let logs_to_insert = weights::sample(logs_to_insert, insert_count, &mut rng, |log| log.weight);
+
+  for log in &logs_to_insert {
+    log::info!("Placing {:?} (weight {})", log.audio, log.weight);
+  }
+
+  // In fill mode the picked logs are cycled through, so each one is used either `n` or `n + 1` times
+  let logs_to_insert: Vec<NewLog> = if options.fill && insert_count < custom_count {
+    logs_to_insert.iter().cycle().take(custom_count).cloned().collect()
+  } else {
+    logs_to_insert
+  };
+
+  // Originals take whichever slots are left over
+  if options.originals_dir.is_some() {
+    seed::shuffle(&mut originals, &mut rng);
+    let remaining = slot_count.saturating_sub(logs_to_insert.len());
+    originals.truncate(remaining);
+  } else {
+    originals.clear();
+  }
+
+  seed::shuffle(&mut logs_data, &mut rng);
+
+  let (custom_placements, logs_data) = options.areas.assign(logs_to_insert, logs_data);
+  placements.extend(custom_placements);
+  placements.extend(
+    std::iter::zip(logs_data, originals)
+      .map(|(slot, log)| log.place(slot))
+  );
+
+  for placement in &placements {
+    log::info!(
+      "Placed {:?} in {} ({})",
+      placement.audio,
+      placement.slot.subtitle,
+      placement.slot.area.as_deref().unwrap_or("unknown area"),
+    );
+  }
+
+  Ok(placements)
+}
+
+// Replaces every audio log in the game with the same sound file
+pub fn feeling_lucky(source_file: &Path, subs_file: &Path) -> Result<Vec<Placement>> {
+  let placements = DataStore::get_logs()?.into_iter()
+    .map(|slot| Placement {
+      slot,
+      audio: source_file.to_owned(),
+      subs: Some(subs_file.to_owned()),
+      weight: Weight::default(),
+      pack: None,
+      info: None,
+    })
+    .collect();
 
+  Ok(placements)
+}
+
+pub fn insertion_maps(placements: &[Placement]) -> (SoundInsertionMap, SubsInsertionMap) {
   let mut inserted_logs: SoundInsertionMap = HashMap::new();
   let mut inserted_subs: SubsInsertionMap = HashMap::new();
-  
-  for (dest_log, src_log) in pairs {
-    let AudioLog { package, filename, subtitle } = dest_log;
-    let NewLog { audio, subs } = src_log;
 
-    let dest_pkg = if let Some(path) = package {
-      SoundDestination::Package(path)
+  for Placement { slot, audio, subs, .. } in placements {
+    let dest_pkg = if let Some(path) = &slot.package {
+      SoundDestination::Package(path.clone())
     } else {
       SoundDestination::Root
     };
 
-    let insertion = SoundInsertion { source_file: audio, dest_file: filename };
+    let insertion = SoundInsertion { source_file: audio.clone(), dest_file: slot.filename.clone() };
 
-    if inserted_logs.contains_key(&dest_pkg) {
-      let d: &mut Vec<SoundInsertion> = inserted_logs.get_mut(&dest_pkg).unwrap();
-      d.push(insertion); 
-    } else {
-      inserted_logs.insert(dest_pkg, vec![insertion]);
-    }
+    inserted_logs.entry(dest_pkg).or_default().push(insertion);
 
-    inserted_subs.insert(subtitle, subs);
+    inserted_subs.insert(slot.subtitle.clone(), subs.clone());
   }
 
   (inserted_logs, inserted_subs)
 }
 
+// Checks the placements can be inserted before the game is modified, naming every offending file.
+// Subtitles that can't be read or would corrupt a subtitles file are always errors; in strict mode
+// so are logs without subtitles and sound files or packages that would make an insertion fail.
+pub fn check_insertion(
+  witness_dir: &Path,
+  placements: &[Placement],
+  fallback: SubtitleFallback,
+  strict: bool,
+) -> Result<()> {
+  let (logs, subs) = insertion_maps(placements);
+  let mut problems = witness_data::subtitle_problems(witness_dir, &subs, fallback)?;
+
+  if strict {
+    for placement in placements.iter().filter(|placement| placement.subs.is_none()) {
+      problems.push(format!("Audio log {:?} has no subtitles file", placement.audio));
+    }
+    problems.extend(witness_data::sound_problems(witness_dir, &logs));
+  }
+
+  problems.sort();
+  problems.dedup();
+  if !problems.is_empty() {
+    return Err(anyhow!("The audio logs can't be inserted:\n  {}", problems.join("\n  ")));
+  }
+
+  Ok(())
+}
+
 // ---------------------------------------------------------------------------------------------------
 
 #[derive(Debug, Clone)]
 pub struct NewLog {
-  audio: PathBuf,
-  subs:  Option<PathBuf>, 
+  name:   String,
+  audio:  PathBuf,
+  subs:   Option<PathBuf>, 
+  weight: Weight,
+  pack:   Option<String>,
+  info:   Option<LogInfo>,
 }
 
-fn get_entries<P: AsRef<Path>>(logs_dir: P) -> Vec<NewLog> {
-  let ogg_extension = std::ffi::OsStr::new("ogg");
+impl NewLog {
+  // The name used to refer to this log in weights and constraints files (see `sources::log_key`)
+  pub fn name(&self) -> &str {
+    &self.name
+  }
 
-  fs::read_dir(logs_dir).unwrap().into_iter()
-    .filter_map(|entry| entry.ok())
-    .map(|entry| entry.path())
-    .filter(|path| path.is_file() && path.extension() == Some(ogg_extension))
-    .map(|ogg| {
-      let mut subs_path = ogg.clone();
-      subs_path.set_extension("sub");
+  fn place(self, slot: AudioLog) -> Placement {
+    let NewLog { audio, subs, weight, pack, info, .. } = self;
+    Placement { slot, audio, subs, weight, pack, info }
+  }
+}
 
-      let subs = if subs_path.exists() {
-        Some(subs_path)
-      } else {
-        None
-      };
+// A fingerprint of the logs in a logs directory that pass the filter, covering their names, packs
+// and weights and the contents of their audio and subtitles files, so that two copies of the same
+// logs give the same fingerprint wherever they are stored
+pub fn fingerprint(logs_dir: &Path, filter: &LogFilter) -> Result<u64> {
+  let mut summary = String::new();
+
+  for log in get_entries(logs_dir, filter)? {
+    let audio = seed::fnv1a_64(&fs::read(&log.audio)?);
+    let subs = match &log.subs {
+      Some(subs) => seed::fnv1a_64(&fs::read(subs)?),
+      None => 0,
+    };
+
+    summary.push_str(&format!(
+      "{}|{}|{:?}|{:016x}|{:016x}\n",
+      log.pack.as_deref().unwrap_or_default(),
+      log.name(),
+      log.weight,
+      audio,
+      subs,
+    ));
+  }
+
+  Ok(seed::fnv1a_64(summary.as_bytes()))
+}
+
+// Entries are in the order `sources::find_logs` returns them, which is sorted by path
+fn get_entries(logs_dir: &Path, filter: &LogFilter) -> Result<Vec<NewLog>> {
+  let weights_manifest = weights::load_manifest(logs_dir).unwrap_or_else(|err| {
+    log::error!("Could not load weights manifest: {:?}", err);
+    HashMap::new()
+  });
+
+  let entries = sources::find_logs(logs_dir, filter)?.into_iter()
+    .map(|source| {
+      let name = source.key();
+      let subs = source.subs.or_else(|| subtitles::find_subs_file(&source.path, None));
+
+      let weight = weights::explicit_weight(&source.path, &name, &weights_manifest)
+        .or(source.weight)
+        .unwrap_or_default();
 
       NewLog {
-        audio: ogg,
-        subs:  subs,
+        name,
+        audio: source.path,
+        subs,
+        weight,
+        pack: source.pack,
+        info: source.info,
       }
-
     })
-    .collect()
+    .collect();
+
+  Ok(entries)
 }
@@ -0,0 +1,243 @@
+use anyhow::{anyhow, Result};
+use serde::{Serialize, Deserialize};
+use std::{
+  fmt,
+  fs,
+  path::PathBuf,
+  str::FromStr,
+};
+
+use crate::core::{
+  constraints::Constraints,
+  randomizer::{AreaRules, Placement, RandomizerOptions},
+  seed,
+  sources::LogFilter,
+};
+
+// ---------------------------------------------------------------------------------------------------
+// Settings strings
+//
+// A settings string holds everything needed to reproduce a run apart from the logs themselves: the
+// seed, the randomizer mode and options, and the constraints. It also holds a fingerprint of the logs
+// it was made with (see `randomizer::fingerprint`), so that using it with different logs is an error
+// rather than a silently different run.
+//
+//   WR1.<payload>.<checksum>
+//
+// `1` is the settings version, the payload is the settings as JSON in unpadded URL-safe base64, and
+// the checksum is the low 32 bits of the FNV-1a hash of everything before it, in hex.
+//
+// The check words are a short hash of the placements themselves (which slot got which audio and
+// subtitles), for players to compare after randomizing.
+
+pub const SETTINGS_VERSION: u32 = 1;
+const SETTINGS_PREFIX: &str = "WR";
+
+const CHECK_WORD_COUNT: usize = 4;
+const CHECK_WORDS: [&str; 64] = [
+  "amber", "anchor", "apple", "arch", "aspen", "basin", "beacon", "birch",
+  "boat", "bridge", "canyon", "cedar", "cliff", "cloud", "copper", "coral",
+  "dawn", "delta", "desert", "dune", "echo", "ember", "fern", "field",
+  "flint", "garden", "glade", "granite", "harbor", "hazel", "island", "ivory",
+  "jade", "lantern", "lotus", "marsh", "meadow", "mirror", "moss", "mountain",
+  "oak", "orchard", "pebble", "pine", "prism", "puzzle", "quarry", "reed",
+  "ridge", "river", "sand", "shadow", "shell", "shrine", "spruce", "stone",
+  "sun", "swamp", "temple", "tide", "tower", "valley", "willow", "windmill",
+];
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+  pub seed: String,
+  pub seed_scheme: u32,
+
+  // Shuffle the game's own logs instead of inserting custom logs
+  #[serde(skip_serializing_if = "is_false")]
+  pub vanilla: bool,
+  #[serde(skip_serializing_if = "is_false")]
+  pub fill: bool,
+
+  // The share of the slots given to custom logs when originals are mixed in (see `CustomShare`)
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub mix: Option<String>,
+
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub include: Vec<String>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub exclude: Vec<String>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub packs: Vec<String>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub disabled_packs: Vec<String>,
+
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub areas: Vec<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub max_per_area: Option<usize>,
+  #[serde(skip_serializing_if = "is_false")]
+  pub spread_packs: bool,
+
+  #[serde(skip_serializing_if = "Constraints::is_empty")]
+  pub constraints: Constraints,
+
+  // Fingerprint of the logs the settings were made with
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub fingerprint: Option<u64>,
+}
+
+fn is_false(value: &bool) -> bool {
+  !value
+}
+
+impl Settings {
+  pub fn new(seed: &str) -> Self {
+    Settings { seed: seed.to_owned(), seed_scheme: seed::SEED_SCHEME_VERSION, ..Default::default() }
+  }
+
+  // The randomizer options for these settings, with the original logs in `originals_dir` if they are
+  // used
+  pub fn options(&self, originals_dir: Option<PathBuf>) -> Result<RandomizerOptions> {
+    let custom_share = match &self.mix {
+      Some(mix) => mix.parse()?,
+      None => Default::default(),
+    };
+
+    Ok(RandomizerOptions {
+      constraints: self.constraints.clone(),
+      filter: LogFilter {
+        include: self.include.clone(),
+        exclude: self.exclude.clone(),
+        enabled_packs: (!self.packs.is_empty()).then(|| self.packs.clone()),
+        disabled_packs: self.disabled_packs.clone(),
+      },
+      fill: self.fill,
+      originals_dir,
+      custom_share,
+      areas: AreaRules {
+        only: (!self.areas.is_empty()).then(|| self.areas.clone()),
+        max_per_area: self.max_per_area,
+        spread_packs: self.spread_packs,
+      },
+    })
+  }
+}
+
+fn checksum(body: &str) -> u32 {
+  seed::fnv1a_64(body.as_bytes()) as u32
+}
+
+impl fmt::Display for Settings {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let json = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+    let body = format!("{}{}.{}", SETTINGS_PREFIX, SETTINGS_VERSION, base64_encode(json.as_bytes()));
+
+    write!(f, "{}.{:08x}", body, checksum(&body))
+  }
+}
+
+impl FromStr for Settings {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    let s = s.trim();
+
+    let (body, check) = s.rsplit_once('.')
+      .ok_or_else(|| anyhow!("`{}` is not a settings string", s))?;
+    let (version, payload) = body.strip_prefix(SETTINGS_PREFIX)
+      .and_then(|rest| rest.split_once('.'))
+      .ok_or_else(|| anyhow!("`{}` is not a settings string", s))?;
+
+    let version: u32 = version.parse().map_err(|_| anyhow!("`{}` is not a settings string", s))?;
+    if version != SETTINGS_VERSION {
+      return Err(anyhow!(
+        "These settings were made with settings version {}, but this version of the randomizer uses version {}",
+        version,
+        SETTINGS_VERSION,
+      ));
+    }
+
+    if u32::from_str_radix(check, 16).ok() != Some(checksum(body)) {
+      return Err(anyhow!("The settings string is mistyped or incomplete"));
+    }
+
+    let json = base64_decode(payload).ok_or_else(|| anyhow!("The settings string is mistyped or incomplete"))?;
+    let settings: Settings = serde_json::from_slice(&json)
+      .map_err(|err| anyhow!("Invalid settings string: {}", err))?;
+
+    if settings.seed_scheme != seed::SEED_SCHEME_VERSION {
+      return Err(anyhow!(
+        "These settings use seed scheme v{}, but this version of the randomizer uses v{}",
+        settings.seed_scheme,
+        seed::SEED_SCHEME_VERSION,
+      ));
+    }
+
+    Ok(settings)
+  }
+}
+
+// A few words hashing which audio and subtitles went into which slot, e.g. `moss-river-tower-dune`
+pub fn check_words(placements: &[Placement]) -> Result<String> {
+  let mut placements: Vec<&Placement> = placements.iter().collect();
+  placements.sort_by(|a, b| a.slot.subtitle.cmp(&b.slot.subtitle));
+
+  let mut summary = String::new();
+  for placement in placements {
+    let audio = seed::fnv1a_64(&fs::read(&placement.audio)?);
+    let subs = match &placement.subs {
+      Some(subs) => seed::fnv1a_64(&fs::read(subs)?),
+      None => 0,
+    };
+
+    summary.push_str(&format!("{}|{:016x}|{:016x}\n", placement.slot.subtitle, audio, subs));
+  }
+
+  let hash = seed::fnv1a_64(summary.as_bytes());
+  let words: Vec<&str> = (0..CHECK_WORD_COUNT)
+    .map(|i| CHECK_WORDS[((hash >> (6 * i)) & 63) as usize])
+    .collect();
+
+  Ok(words.join("-"))
+}
+
+// ---------------------------------------------------------------------------------------------------
+// Unpadded URL-safe base64
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn base64_encode(bytes: &[u8]) -> String {
+  let mut encoded = String::new();
+
+  for chunk in bytes.chunks(3) {
+    let bits = chunk.iter().enumerate()
+      .fold(0u32, |bits, (i, byte)| bits | ((*byte as u32) << (16 - 8 * i)));
+
+    for i in 0..=chunk.len() {
+      encoded.push(BASE64_ALPHABET[((bits >> (18 - 6 * i)) & 63) as usize] as char);
+    }
+  }
+
+  encoded
+}
+
+fn base64_decode(s: &str) -> Option<Vec<u8>> {
+  let mut decoded = Vec::new();
+
+  for chunk in s.as_bytes().chunks(4) {
+    if chunk.len() < 2 {
+      return None;
+    }
+
+    let mut bits = 0u32;
+    for (i, c) in chunk.iter().enumerate() {
+      let value = BASE64_ALPHABET.iter().position(|a| a == c)? as u32;
+      bits |= value << (18 - 6 * i);
+    }
+
+    for i in 0..chunk.len() - 1 {
+      decoded.push((bits >> (16 - 8 * i)) as u8);
+    }
+  }
+
+  Some(decoded)
+}
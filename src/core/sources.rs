@@ -0,0 +1,213 @@
+use anyhow::{anyhow, Result};
+use glob::{MatchOptions, Pattern};
+use std::{
+  collections::BTreeMap,
+  path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+use crate::core::{
+  pack,
+  pack::LogInfo,
+  weights::Weight,
+};
+
+// ---------------------------------------------------------------------------------------------------
+// Finding the audio logs in a logs directory
+//
+// The logs directory is scanned recursively for `.ogg` and `.oga` files, in any case. Each folder
+// directly inside the logs directory is a pack named after the folder, containing every log below
+// it; logs at the top level are not part of any pack, and are always used unless filtered out.
+//
+// Zip files with a `pack.json` manifest (see `pack.rs`) are read as if they were a folder with the
+// same name as the zip file, so `lectures.zip` at the top level is the pack `lectures`. The logs
+// directory can also be a single pack file.
+//
+// Include and exclude filters are glob patterns matched case-insensitively against the path of the
+// log relative to the logs directory, with `/` as the separator (e.g. `feynman/*` or `*_intro.ogg`).
+// When there are include patterns, only logs matching one of them are used. Exclude patterns take
+// precedence over include patterns.
+//
+// Weights and constraints files name logs by the same relative path, without the audio extension
+// (e.g. `feynman/wine` for `feynman/wine.OGG`), so logs with the same name in different packs can be
+// told apart.
+
+pub const LOG_EXTENSIONS: [&str; 2] = ["ogg", "oga"];
+
+#[derive(Debug, Default, Clone)]
+pub struct LogFilter {
+  pub include: Vec<String>,
+  pub exclude: Vec<String>,
+
+  // When set, only the logs in these packs (and the top-level logs) are used
+  pub enabled_packs: Option<Vec<String>>,
+  pub disabled_packs: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceLog {
+  pub path: PathBuf,
+  pub relative_path: PathBuf,
+  pub pack: Option<String>,
+
+  // Set for logs read from pack files, which list their subtitles, weight and info in the manifest
+  pub subs: Option<PathBuf>,
+  pub weight: Option<Weight>,
+  pub info: Option<LogInfo>,
+}
+
+impl SourceLog {
+  // The name this log goes by in weights and constraints files
+  pub fn key(&self) -> String {
+    log_key(&filter_path(&self.relative_path))
+  }
+}
+
+// A log name as written in a weights or constraints file, with `/` separators and without the audio
+// extension, in any case
+pub fn log_key(name: &str) -> String {
+  let name = name.replace('\\', "/");
+
+  match name.rsplit_once('.') {
+    Some((stem, extension)) if LOG_EXTENSIONS.iter().any(|log_ext| extension.eq_ignore_ascii_case(log_ext)) => {
+      stem.to_owned()
+    },
+    _ => name,
+  }
+}
+
+impl LogFilter {
+  pub fn is_empty(&self) -> bool {
+    self.include.is_empty() && self.exclude.is_empty() && self.enabled_packs.is_none() && self.disabled_packs.is_empty()
+  }
+
+  pub fn pack_enabled(&self, pack: &str) -> bool {
+    let enabled = self.enabled_packs.as_ref()
+      .is_none_or(|packs| packs.iter().any(|enabled| enabled.eq_ignore_ascii_case(pack)));
+
+    enabled && !self.disabled_packs.iter().any(|disabled| disabled.eq_ignore_ascii_case(pack))
+  }
+}
+
+fn is_log_file(path: &Path) -> bool {
+  path.extension()
+    .map(|ext| LOG_EXTENSIONS.iter().any(|log_ext| ext.eq_ignore_ascii_case(log_ext)))
+    .unwrap_or(false)
+}
+
+fn compile_patterns(patterns: &[String]) -> Result<Vec<Pattern>> {
+  patterns.iter()
+    .map(|pattern| Pattern::new(pattern).map_err(|err| anyhow!("Invalid pattern `{}`: {}", pattern, err)))
+    .collect()
+}
+
+// The relative path as matched by filters, with `/` separators on every platform
+fn filter_path(relative_path: &Path) -> String {
+  relative_path.components()
+    .map(|component| component.as_os_str().to_string_lossy())
+    .collect::<Vec<_>>()
+    .join("/")
+}
+
+fn pack_of(relative_path: &Path) -> Option<String> {
+  let mut components = relative_path.components();
+  let first = components.next()?;
+
+  // Only logs below a folder are in a pack
+  components.next()?;
+  Some(first.as_os_str().to_string_lossy().to_string())
+}
+
+// All the logs in a logs directory that pass the filter, sorted by relative path, since walk order
+// differs between filesystems and the same seed has to give the same placements everywhere
+pub fn find_logs(logs_dir: &Path, filter: &LogFilter) -> Result<Vec<SourceLog>> {
+  let include = compile_patterns(&filter.include)?;
+  let exclude = compile_patterns(&filter.exclude)?;
+  let options = MatchOptions { case_sensitive: false, ..MatchOptions::new() };
+
+  let mut found: Vec<SourceLog> = Vec::new();
+
+  if logs_dir.is_file() && pack::is_pack_file(logs_dir) {
+    found.extend(pack_logs(logs_dir, Path::new(""))?);
+  } else if logs_dir.is_dir() {
+    for entry in WalkDir::new(logs_dir).into_iter().filter_map(|entry| entry.ok()) {
+      let path = entry.into_path();
+      let relative_path = match path.strip_prefix(logs_dir) {
+        Ok(relative_path) => relative_path.to_owned(),
+        Err(_) => continue,
+      };
+
+      if !path.is_file() {
+        continue;
+      } else if is_log_file(&path) {
+        let pack = pack_of(&relative_path);
+        found.push(SourceLog { path, relative_path, pack, subs: None, weight: None, info: None });
+      } else if pack::is_pack_file(&path) {
+        // Other zip files may be lying around in the logs directory, so only packs with an invalid
+        // manifest are errors
+        if !pack::has_manifest(&path)? {
+          log::warn!("Skipping {:?}: it has no {} and is not an audio log pack", path, pack::PACK_MANIFEST);
+          continue;
+        }
+
+        found.extend(pack_logs(&path, relative_path.parent().unwrap_or(Path::new("")))?);
+      }
+    }
+  } else {
+    return Err(anyhow!("Audio logs directory {:?} does not exist", logs_dir));
+  }
+
+  let mut logs: Vec<SourceLog> = found.into_iter()
+    .filter(|log| {
+      if let Some(pack) = &log.pack {
+        if !filter.pack_enabled(pack) {
+          return false;
+        }
+      }
+
+      let name = filter_path(&log.relative_path);
+      let included = include.is_empty() || include.iter().any(|pattern| pattern.matches_with(&name, options));
+      let excluded = exclude.iter().any(|pattern| pattern.matches_with(&name, options));
+
+      included && !excluded
+    })
+    .collect();
+
+  logs.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+  Ok(logs)
+}
+
+// The logs in a pack file, placed in a folder named after the pack inside `parent`
+fn pack_logs(pack_path: &Path, parent: &Path) -> Result<Vec<SourceLog>> {
+  let pack_dir = parent.join(pack_path.file_stem().unwrap_or_default());
+
+  let logs = pack::load_pack(pack_path)?.into_iter()
+    .map(|log| {
+      let relative_path = pack_dir.join(&log.file);
+
+      SourceLog {
+        path: log.audio,
+        pack: pack_of(&relative_path),
+        relative_path,
+        subs: log.subs,
+        weight: log.weight,
+        info: Some(log.info),
+      }
+    })
+    .collect();
+
+  Ok(logs)
+}
+
+// The packs in a logs directory, with the number of logs in each
+pub fn list_packs(logs_dir: &Path) -> Result<BTreeMap<String, usize>> {
+  let mut packs: BTreeMap<String, usize> = BTreeMap::new();
+
+  for log in find_logs(logs_dir, &LogFilter::default())? {
+    if let Some(pack) = log.pack {
+      *packs.entry(pack).or_default() += 1;
+    }
+  }
+
+  Ok(packs)
+}
@@ -0,0 +1,155 @@
+use anyhow::{anyhow, Result};
+use serde::{Serialize, Deserialize};
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use crate::core::{
+  pack::LogInfo,
+  randomizer::Placement,
+  seed,
+};
+
+// ---------------------------------------------------------------------------------------------------
+// Spoiler logs record which custom log ended up in which slot. The spoiler for the most recent run is
+// kept in the install directory, both as JSON and as a human-readable text file.
+
+fn spoiler_json_path(witness_dir: &Path) -> PathBuf { witness_dir.join("randomizer_spoiler.json") }
+fn spoiler_text_path(witness_dir: &Path) -> PathBuf { witness_dir.join("randomizer_spoiler.txt") }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpoilerLog {
+  pub seed: Option<String>,
+  pub seed_scheme: u32,
+
+  // The settings string and check words of the run (see `settings.rs`)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub settings: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub check: Option<String>,
+
+  pub placements: Vec<SpoilerEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpoilerEntry {
+  pub source: PathBuf,
+  pub source_subs: Option<PathBuf>,
+  pub package: Option<PathBuf>,
+  pub filename: PathBuf,
+  pub subtitle: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub area: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub pack: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub info: Option<LogInfo>,
+}
+
+impl SpoilerLog {
+  pub fn new(seed: Option<&str>, placements: &[Placement]) -> Self {
+    let mut placements: Vec<SpoilerEntry> = placements.iter()
+      .map(|Placement { slot, audio, subs, pack, info, .. }| SpoilerEntry {
+        source: audio.clone(),
+        source_subs: subs.clone(),
+        package: slot.package.clone(),
+        filename: slot.filename.clone(),
+        subtitle: slot.subtitle.clone(),
+        area: slot.area.clone(),
+        pack: pack.clone(),
+        info: info.clone(),
+      })
+      .collect();
+    placements.sort_by(|a, b| a.subtitle.cmp(&b.subtitle));
+
+    SpoilerLog {
+      seed: seed.map(str::to_owned),
+      seed_scheme: seed::SEED_SCHEME_VERSION,
+      settings: None,
+      check: None,
+      placements,
+    }
+  }
+
+  pub fn to_text(&self) -> String {
+    let mut lines = vec![String::from("Witness Audio Log Randomizer spoiler log")];
+
+    match &self.seed {
+      Some(seed) => lines.push(format!("Seed: {} (seed scheme v{})", seed, self.seed_scheme)),
+      None       => lines.push(String::from("Seed: none")),
+    }
+    if let Some(settings) = &self.settings {
+      lines.push(format!("Settings: {}", settings));
+    }
+    if let Some(check) = &self.check {
+      lines.push(format!("Check: {}", check));
+    }
+    lines.push(format!("Placements: {}", self.placements.len()));
+    lines.push(String::new());
+
+    for entry in &self.placements {
+      let destination = match &entry.package {
+        Some(package) => format!("{}/{}", package.display(), entry.filename.display()),
+        None          => format!("data-pc/{}", entry.filename.display()),
+      };
+      let source = entry.source.file_name().map(Path::new).unwrap_or(&entry.source);
+
+      lines.push(entry.subtitle.clone());
+      lines.push(format!("  slot:   {}", destination));
+      if let Some(area) = &entry.area {
+        lines.push(format!("  area:   {}", area));
+      }
+      lines.push(format!("  source: {}", source.display()));
+
+      if let Some(pack) = &entry.pack {
+        lines.push(format!("  pack:   {}", pack));
+      }
+      if let Some(info) = &entry.info {
+        if let Some(title) = &info.title {
+          lines.push(format!("  title:  {}", title));
+        }
+        if let Some(speaker) = &info.speaker {
+          lines.push(format!("  by:     {}", speaker));
+        }
+        if let Some(author) = &info.author {
+          lines.push(format!("  author: {}", author));
+        }
+        if !info.tags.is_empty() {
+          lines.push(format!("  tags:   {}", info.tags.join(", ")));
+        }
+      }
+    }
+
+    lines.join("\n") + "\n"
+  }
+}
+
+pub fn write_spoiler(witness_dir: &Path, spoiler: &SpoilerLog) -> Result<()> {
+  let json = serde_json::to_string_pretty(spoiler)?;
+  fs::write(spoiler_json_path(witness_dir), json)?;
+
+  // Windows line endings, so the file opens properly in Notepad
+  let text = spoiler.to_text().replace('\n', "\r\n");
+  fs::write(spoiler_text_path(witness_dir), text)?;
+
+  Ok(())
+}
+
+pub fn read_spoiler(witness_dir: &Path) -> Result<SpoilerLog> {
+  let path = spoiler_json_path(witness_dir);
+
+  let raw = fs::read_to_string(&path)
+    .map_err(|err| anyhow!("Could not read spoiler log {:?}: {}", path, err))?;
+  serde_json::from_str(&raw).map_err(anyhow::Error::from)
+}
+
+pub fn remove_spoiler(witness_dir: &Path) -> Result<()> {
+  for path in [spoiler_json_path(witness_dir), spoiler_text_path(witness_dir)] {
+    if path.exists() {
+      fs::remove_file(path)?;
+    }
+  }
+
+  Ok(())
+}
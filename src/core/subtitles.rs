@@ -0,0 +1,378 @@
+use anyhow::{anyhow, Result};
+use std::{
+  fmt,
+  fs,
+  path::{Path, PathBuf},
+};
+
+// ---------------------------------------------------------------------------------------------------
+// The game's `.subtitles` files
+//
+// A subtitles file is a list of entries, each starting with a line beginning with `:` followed by
+// the subtitle key, with the text of the subtitle on the lines up to the next entry:
+//
+//   : schweickart_eva
+//
+//   Up there you go around every hour and a half, time after time after time...
+//
+//
+// The game writes CRLF line endings and pads the text with blank lines, but files edited by hand
+// may use LF line endings or start with a byte order mark. `SubtitlesFile` keeps every byte of the
+// file, so writing it back without changes gives the same bytes, and changing the text of an entry
+// only touches that entry's text, leaving the blank lines around it as they were.
+//
+// The `.sub` files next to custom logs are cleaned up before their text goes into a subtitles file
+// (see `read_sub_file`): they may be UTF-8 or UTF-16 (with a byte order mark), use any line endings,
+// and have blank lines around the text, but no line of the text may start with `:`, since the game
+// would read it as the key of a new subtitle. SRT and WebVTT transcripts can be used instead of a
+// `.sub` file, and are converted to plain text with each cue as a paragraph.
+
+const BOM: &str = "\u{feff}";
+
+// Extensions of the subtitles files next to custom logs, in the order they are looked for
+pub const SUBTITLE_EXTENSIONS: [&str; 4] = ["sub", "srt", "vtt", "txt"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubtitlesFile {
+  bom: bool,
+
+  // Anything before the first entry
+  preamble: String,
+  entries: Vec<SubtitleEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubtitleEntry {
+  key: String,
+
+  // The raw key line, including the `:` and the line ending
+  header: String,
+
+  // The raw text of the entry, up to the next key line
+  body: String,
+}
+
+// Where and why a subtitles file could not be parsed. Lines and columns count from 1, with columns
+// counted in characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+  pub line: usize,
+  pub column: usize,
+  pub message: String,
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+  }
+}
+
+impl std::error::Error for ParseError {}
+
+impl SubtitleEntry {
+  pub fn key(&self) -> &str {
+    &self.key
+  }
+
+  // The subtitle text, without the blank lines around it
+  pub fn text(&self) -> &str {
+    self.body.trim()
+  }
+
+  fn line_ending(&self) -> &str {
+    if self.header.ends_with("\r\n") || !self.header.ends_with('\n') { "\r\n" } else { "\n" }
+  }
+
+  // Replaces the text, keeping the blank lines before and after it. The text is given the line
+  // endings of the entry.
+  pub fn set_text(&mut self, text: &str) {
+    let line_ending = self.line_ending().to_owned();
+    if !self.header.ends_with('\n') {
+      self.header.push_str(&line_ending);
+    }
+
+    let text = normalize_line_endings(text.trim()).replace('\n', &line_ending);
+    let text = text.as_str();
+    let (before, after) = if self.text().is_empty() {
+      // Without any text to replace, the text goes after the first blank line, as the game has it
+      match self.body.find('\n') {
+        Some(index) => self.body.split_at(index + 1),
+        None => ("", ""),
+      }
+    } else {
+      let start = self.body.len() - self.body.trim_start().len();
+      let end = self.body.trim_end().len();
+      (&self.body[..start], &self.body[end..])
+    };
+
+    let after = if after.is_empty() { line_ending.repeat(3) } else { after.to_owned() };
+    let before = if before.is_empty() && !self.body.contains('\n') { line_ending } else { before.to_owned() };
+
+    self.body = format!("{}{}{}", before, text, after);
+  }
+}
+
+impl SubtitlesFile {
+  pub fn parse(bytes: &[u8]) -> Result<Self, ParseError> {
+    let raw = std::str::from_utf8(bytes).map_err(|err| {
+      let valid = std::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap_or_default();
+      let (line, column) = position(valid.strip_prefix(BOM).unwrap_or(valid));
+      ParseError { line, column, message: String::from("invalid UTF-8") }
+    })?;
+
+    let (bom, raw) = match raw.strip_prefix(BOM) {
+      Some(rest) => (true, rest),
+      None => (false, raw),
+    };
+
+    let mut preamble = String::new();
+    let mut entries: Vec<SubtitleEntry> = Vec::new();
+
+    for (index, line) in raw.split_inclusive('\n').enumerate() {
+      if let Some(rest) = line.strip_prefix(':') {
+        let key = rest.trim();
+        if key.is_empty() {
+          return Err(ParseError { line: index + 1, column: 2, message: String::from("expected a subtitle key after `:`") });
+        }
+
+        entries.push(SubtitleEntry { key: key.to_owned(), header: line.to_owned(), body: String::new() });
+      } else {
+        match entries.last_mut() {
+          Some(entry) => entry.body.push_str(line),
+          None => preamble.push_str(line),
+        }
+      }
+    }
+
+    Ok(SubtitlesFile { bom, preamble, entries })
+  }
+
+  pub fn read(path: &Path) -> Result<Self> {
+    let bytes = fs::read(path)
+      .map_err(|err| anyhow!("Could not read subtitles file {:?}: {}", path, err))?;
+
+    SubtitlesFile::parse(&bytes).map_err(|err| anyhow!("Could not parse subtitles file {:?}: {}", path, err))
+  }
+
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut raw = String::new();
+
+    if self.bom {
+      raw.push_str(BOM);
+    }
+    raw.push_str(&self.preamble);
+    for entry in &self.entries {
+      raw.push_str(&entry.header);
+      raw.push_str(&entry.body);
+    }
+
+    raw.into_bytes()
+  }
+
+  pub fn write(&self, path: &Path) -> Result<()> {
+    fs::write(path, self.to_bytes())
+      .map_err(|err| anyhow!("Could not write subtitles file {:?}: {}", path, err))
+  }
+
+  pub fn entries(&self) -> &[SubtitleEntry] {
+    &self.entries
+  }
+
+  pub fn get(&self, key: &str) -> Option<&str> {
+    self.entries.iter().find(|entry| entry.key == key).map(SubtitleEntry::text)
+  }
+
+  // Sets the text of every entry with this key, returning whether there were any
+  pub fn set_text(&mut self, key: &str, text: &str) -> bool {
+    let mut found = false;
+
+    for entry in self.entries.iter_mut().filter(|entry| entry.key == key) {
+      entry.set_text(text);
+      found = true;
+    }
+
+    found
+  }
+}
+
+// ---------------------------------------------------------------------------------------------------
+// Subtitle text from `.sub` files and transcripts
+
+// The subtitles file next to a log (or to another subtitles file), e.g. `foo.srt` for `foo.ogg`, or
+// `foo.de.sub` for `foo.ogg` in German
+pub fn find_subs_file(path: &Path, language: Option<&str>) -> Option<PathBuf> {
+  SUBTITLE_EXTENSIONS.iter()
+    .map(|extension| match language {
+      Some(language) => path.with_extension(format!("{}.{}", language, extension)),
+      None => path.with_extension(extension),
+    })
+    .find(|subs| subs.is_file())
+}
+
+// Reads the text of a `.sub` file or transcript, ready to be inserted with `set_text`
+pub fn read_sub_file(path: &Path) -> Result<String> {
+  let bytes = fs::read(path)
+    .map_err(|err| anyhow!("Could not read subtitles file {:?}: {}", path, err))?;
+
+  let is = |format: &str| path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case(format));
+
+  decode_text(&bytes)
+    .and_then(|text| {
+      if is("srt") {
+        Ok(srt_to_text(&text))
+      } else if is("vtt") {
+        vtt_to_text(&text)
+      } else {
+        Ok(text)
+      }
+    })
+    .and_then(|text| clean_text(&text))
+    .map_err(|err| anyhow!("Subtitles file {:?}: {}", path, err))
+}
+
+// The text of an SRT transcript, without cue numbers, timestamps or formatting
+pub fn srt_to_text(text: &str) -> String {
+  let text = normalize_line_endings(text);
+  let cues = blocks(&text).into_iter()
+    .map(|lines| {
+      let text = match lines.iter().position(|line| line.contains("-->")) {
+        Some(timing) => &lines[timing + 1..],
+        None => &lines[..],
+      };
+      cue_text(text, false)
+    });
+
+  join_cues(cues)
+}
+
+// The text of a WebVTT transcript, without the header, cue identifiers, timestamps, notes, styles or
+// formatting
+pub fn vtt_to_text(text: &str) -> Result<String> {
+  let text = normalize_line_endings(text);
+  let blocks = blocks(&text);
+
+  let header = blocks.first().and_then(|lines| lines.first()).copied().unwrap_or_default();
+  if header != "WEBVTT" && !header.starts_with("WEBVTT ") && !header.starts_with("WEBVTT\t") {
+    return Err(anyhow!("line 1: expected a WebVTT file to start with `WEBVTT`"));
+  }
+
+  // Blocks without a timing line are notes, styles and regions rather than cues
+  let cues = blocks.into_iter().skip(1)
+    .filter_map(|lines| {
+      let timing = lines.iter().position(|line| line.contains("-->"))?;
+      Some(cue_text(&lines[timing + 1..], true))
+    });
+
+  Ok(join_cues(cues))
+}
+
+// The blocks of lines between blank lines, in text with `\n` line endings
+fn blocks(text: &str) -> Vec<Vec<&str>> {
+  let text = text.strip_prefix(BOM).unwrap_or(text);
+  let mut blocks: Vec<Vec<&str>> = vec![Vec::new()];
+
+  for line in text.split('\n').map(str::trim) {
+    match blocks.last_mut() {
+      Some(block) if !line.is_empty() => block.push(line),
+      Some(block) if !block.is_empty() => blocks.push(Vec::new()),
+      _ => {},
+    }
+  }
+
+  blocks.retain(|block| !block.is_empty());
+  blocks
+}
+
+// The lines of a cue without tags (`<i>`, `<v Speaker>`, `{\an8}`), and with WebVTT's character
+// references decoded
+fn cue_text(lines: &[&str], references: bool) -> String {
+  lines.iter()
+    .map(|line| {
+      let mut text = String::new();
+      let mut closing = None;
+      let mut chars = line.chars().peekable();
+
+      while let Some(c) = chars.next() {
+        match closing {
+          Some(end) if c == end => closing = None,
+          Some(_) => {},
+          None if c == '<' => closing = Some('>'),
+          None if c == '{' && !references && chars.peek() == Some(&'\\') => closing = Some('}'),
+          None => text.push(c),
+        }
+      }
+
+      if references {
+        text = text.replace("&lt;", "<").replace("&gt;", ">").replace("&nbsp;", "\u{a0}")
+          .replace("&lrm;", "\u{200e}").replace("&rlm;", "\u{200f}").replace("&amp;", "&");
+      }
+
+      text.trim().to_owned()
+    })
+    .filter(|line| !line.is_empty())
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
+fn join_cues(cues: impl Iterator<Item = String>) -> String {
+  cues.filter(|cue| !cue.is_empty()).collect::<Vec<String>>().join("\n\n")
+}
+
+// Decodes UTF-16 text with a byte order mark, and UTF-8 text with or without one
+pub fn decode_text(bytes: &[u8]) -> Result<String> {
+  if let Some(rest) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+    decode_utf8(rest)
+  } else if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+    decode_utf16(rest, u16::from_le_bytes)
+  } else if let Some(rest) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+    decode_utf16(rest, u16::from_be_bytes)
+  } else {
+    decode_utf8(bytes)
+  }
+}
+
+fn decode_utf8(bytes: &[u8]) -> Result<String> {
+  match std::str::from_utf8(bytes) {
+    Ok(text) => Ok(text.to_owned()),
+    Err(err) => {
+      let valid = std::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap_or_default();
+      let (line, column) = position(valid);
+      Err(anyhow!("line {}, column {}: invalid UTF-8 (UTF-16 files need a byte order mark)", line, column))
+    },
+  }
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Result<String> {
+  if !bytes.len().is_multiple_of(2) {
+    return Err(anyhow!("invalid UTF-16: odd number of bytes"));
+  }
+
+  let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| from_bytes([pair[0], pair[1]])).collect();
+  String::from_utf16(&units).map_err(|_| anyhow!("invalid UTF-16"))
+}
+
+// Converts line endings to `\n`, trims the text and the end of each line, and checks that no line
+// starts with `:`
+pub fn clean_text(text: &str) -> Result<String> {
+  let text = normalize_line_endings(text);
+  let lines: Vec<&str> = text.trim().lines().map(str::trim_end).collect();
+
+  if let Some(index) = lines.iter().position(|line| line.starts_with(':')) {
+    let blank_lines = text[..text.len() - text.trim_start().len()].matches('\n').count();
+    return Err(anyhow!("line {} starts with `:`, which would start a new subtitle", blank_lines + index + 1));
+  }
+
+  Ok(lines.join("\n"))
+}
+
+fn normalize_line_endings(text: &str) -> String {
+  text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+// The line and column just after `text`
+fn position(text: &str) -> (usize, usize) {
+  let line = text.matches('\n').count() + 1;
+  let column = text.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+
+  (line, column)
+}
@@ -27,8 +27,8 @@ pub fn ogg_to_sound(source_file: &Path, dest_file: &Path) -> Result<()> {
     0x0B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00
   ];
   
-  outfile.write(&header_bytes)?;
-  outfile.write(&infile_size_32.to_le_bytes())?;
+  outfile.write_all(&header_bytes)?;
+  outfile.write_all(&infile_size_32.to_le_bytes())?;
   io::copy(&mut infile, &mut outfile)?;
 
   Ok(())
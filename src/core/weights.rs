@@ -0,0 +1,175 @@
+use anyhow::{anyhow, Result};
+use std::{
+  collections::HashMap,
+  fmt,
+  fs,
+  path::Path,
+  str::FromStr,
+};
+
+use crate::core::{
+  seed::{self, SeedRng},
+  sources,
+};
+
+// ---------------------------------------------------------------------------------------------------
+// Placement weights
+//
+// Each audio log has a weight controlling how likely it is to be picked when there are more logs
+// than slots. Weights are read from a `<name>.weight` sidecar file next to the `.ogg`, or from a
+// `weights.json` manifest in the logs directory mapping the paths of logs relative to the logs
+// directory (with or without the audio extension, see `sources.rs`) to weights. Logs from packs can
+// also have a weight in the pack manifest. Sidecar files take precedence over the `weights.json`
+// manifest, which takes precedence over pack manifests, and logs without any weight have a weight
+// of 1.
+//
+// A weight is either a non-negative number, or `always` for logs that must always be placed. Logs
+// with a weight of 0 are never placed.
+
+pub const WEIGHTS_MANIFEST: &str = "weights.json";
+pub const WEIGHT_EXTENSION: &str = "weight";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Weight {
+  Always,
+  Relative(f64),
+}
+
+impl Default for Weight {
+  fn default() -> Self {
+    Weight::Relative(1.0)
+  }
+}
+
+impl fmt::Display for Weight {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Weight::Always => write!(f, "always"),
+      Weight::Relative(weight) => write!(f, "{}", weight),
+    }
+  }
+}
+
+impl FromStr for Weight {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    let s = s.trim();
+
+    if s.eq_ignore_ascii_case("always") {
+      return Ok(Weight::Always);
+    }
+
+    let weight: f64 = s.parse().map_err(|_| anyhow!("Invalid weight `{}`", s))?;
+    Weight::from_number(weight)
+  }
+}
+
+impl Weight {
+  fn from_number(weight: f64) -> Result<Self> {
+    if weight.is_finite() && weight >= 0.0 {
+      Ok(Weight::Relative(weight))
+    } else {
+      Err(anyhow!("Invalid weight `{}`, weights must be non-negative numbers", weight))
+    }
+  }
+
+  pub fn is_never(&self) -> bool {
+    *self == Weight::Relative(0.0)
+  }
+}
+
+// Weights listed in the `weights.json` manifest of a logs directory, keyed by log name (see
+// `sources::log_key`)
+pub fn load_manifest(logs_dir: &Path) -> Result<HashMap<String, Weight>> {
+  let path = logs_dir.join(WEIGHTS_MANIFEST);
+  if !path.exists() {
+    return Ok(HashMap::new());
+  }
+
+  let raw = fs::read_to_string(&path)?;
+  let entries: HashMap<String, serde_json::Value> = serde_json::from_str(&raw)
+    .map_err(|err| anyhow!("Could not parse {:?}: {}", path, err))?;
+
+  entries.into_iter()
+    .map(|(name, value)| {
+      let weight = weight_from_json(&value);
+      let name = sources::log_key(&name);
+
+      weight
+        .map(|weight| (name.clone(), weight))
+        .map_err(|err| anyhow!("{:?}, entry `{}`: {}", path, name, err))
+    })
+    .collect()
+}
+
+// A weight given as a JSON number or string
+pub fn weight_from_json(value: &serde_json::Value) -> Result<Weight> {
+  match value {
+    serde_json::Value::Number(n) => Weight::from_number(n.as_f64().unwrap_or(f64::NAN)),
+    serde_json::Value::String(s) => s.parse(),
+    _ => Err(anyhow!("Invalid weight `{}`", value)),
+  }
+}
+
+// The weight set for an audio log by its sidecar file or the directory manifest, if any. `key` is
+// the name of the log in the manifest.
+pub fn explicit_weight(ogg: &Path, key: &str, manifest: &HashMap<String, Weight>) -> Option<Weight> {
+  let sidecar = ogg.with_extension(WEIGHT_EXTENSION);
+
+  if sidecar.is_file() {
+    let parsed = fs::read_to_string(&sidecar)
+      .map_err(anyhow::Error::from)
+      .and_then(|raw| raw.parse());
+
+    match parsed {
+      Ok(weight) => return Some(weight),
+      Err(err) => log::error!("Ignoring weight file {:?}: {:?}", sidecar, err),
+    }
+  }
+
+  manifest.get(key).copied()
+}
+
+// ---------------------------------------------------------------------------------------------------
+
+// Weighted sampling without replacement. `items` should already be shuffled. Items weighted `always`
+// are picked first; the remaining picks are drawn one at a time with probability proportional to
+// their weight among the items not picked yet. When every item has the same relative weight this
+// is uniform sampling, and the first `count` items are taken without drawing from the RNG, which
+// keeps seeds for unweighted log directories unchanged.
+pub fn sample<T, F: Fn(&T) -> Weight>(items: Vec<T>, count: usize, rng: &mut SeedRng, weight_of: F) -> Vec<T> {
+  let (always, mut rest): (Vec<T>, Vec<T>) = items.into_iter()
+    .filter(|item| !weight_of(item).is_never())
+    .partition(|item| weight_of(item) == Weight::Always);
+
+  let mut picked: Vec<T> = always.into_iter().take(count).collect();
+  let remaining = count.saturating_sub(picked.len()).min(rest.len());
+
+  let relative = |item: &T| match weight_of(item) {
+    Weight::Relative(weight) => weight,
+    Weight::Always => 0.0,
+  };
+
+  let uniform = rest.windows(2).all(|pair| relative(&pair[0]) == relative(&pair[1]));
+  if uniform {
+    picked.extend(rest.into_iter().take(remaining));
+    return picked;
+  }
+
+  for _ in 0..remaining {
+    let total: f64 = rest.iter().map(relative).sum();
+    let mut target = seed::gen_unit(rng) * total;
+
+    let index = rest.iter()
+      .position(|item| {
+        target -= relative(item);
+        target < 0.0
+      })
+      .unwrap_or(rest.len() - 1);
+
+    picked.push(rest.remove(index));
+  }
+
+  picked
+}
@@ -1,26 +1,67 @@
 use anyhow::{anyhow, Result};
-use regex::Regex;
 use rust_embed::RustEmbed;
 use serde::{Serialize, Deserialize};
 use std::{
-  collections::HashMap,
+  collections::{BTreeSet, HashMap},
+  fmt,
   fs,
   io::Write,
   path::{Path, PathBuf},
+  str::FromStr,
 };
 
 use crate::core::{
+  subtitles::{self, SubtitlesFile},
   util,
   zip,
 };
 
 // ---------------------------------------------------------------------------------------------------
 
+// One of the game's audio log slots. Besides where the log is stored, a slot can record the area of
+// the island it is in, for the area rules of the randomizer. The embedded `logs.json` has no areas,
+// so they come from slot data overlays (see `SLOTS_FILE`).
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct AudioLog {
   pub package:  Option<PathBuf>,
   pub filename: PathBuf,
   pub subtitle: String,
+
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub area:     Option<String>,
+}
+
+// What custom logs show in a language they have no subtitles file for
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleFallback {
+  // The English subtitles of the log
+  #[default]
+  English,
+  // No subtitles
+  Blank,
+}
+
+impl fmt::Display for SubtitleFallback {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SubtitleFallback::English => write!(f, "english"),
+      SubtitleFallback::Blank   => write!(f, "blank"),
+    }
+  }
+}
+
+impl FromStr for SubtitleFallback {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s.trim().to_ascii_lowercase().as_str() {
+      "english" | "en" => Ok(SubtitleFallback::English),
+      "blank" | "none" => Ok(SubtitleFallback::Blank),
+      _ => Err(anyhow!("Unknown subtitle fallback `{}`, expected `english` or `blank`", s)),
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -29,19 +70,107 @@ pub struct Subtitle {
   pub val: String,
 }
 
+// Slot data can be corrected or extended without rebuilding: a `logs.json` file in the config
+// directory, then every `.json` file in its `logs.d` folder in name order, are merged over the
+// embedded `logs.json`. They use the same format as the embedded file. Entries are matched to slots
+// by `subtitle` and only change the fields they list (`null` clears a field); entries for unknown
+// subtitle keys add a new slot, and need at least a `filename`.
+pub const SLOTS_FILE: &str = "logs.json";
+pub const SLOTS_OVERLAY_DIR: &str = "logs.d";
+
 #[derive(RustEmbed)]
 #[folder = "data/"]
 pub struct DataStore;
 
 impl DataStore {
-  pub fn get_logs() -> Vec<AudioLog> {
-    let logs_file = DataStore::get("logs.json").unwrap();
-    let s = std::str::from_utf8(logs_file.data.as_ref()).unwrap();
+  // The slot data, with the overlays in the config directory (the working directory, like
+  // `config.json`) applied
+  pub fn get_logs() -> Result<Vec<AudioLog>> {
+    DataStore::load_logs(Path::new(""))
+  }
+
+  pub fn embedded_logs() -> Result<Vec<AudioLog>> {
+    let logs_file = DataStore::get(SLOTS_FILE)
+      .ok_or_else(|| anyhow!("Embedded {} is missing", SLOTS_FILE))?;
+    let s = std::str::from_utf8(logs_file.data.as_ref())?;
+
+    serde_json::from_str(s).map_err(|err| anyhow!("Embedded {} is invalid: {}", SLOTS_FILE, err))
+  }
+
+  // The embedded slot data with the overlays in `config_dir` applied. Every problem in the overlays
+  // is reported at once.
+  pub fn load_logs(config_dir: &Path) -> Result<Vec<AudioLog>> {
+    let mut logs = DataStore::embedded_logs()?;
+    let mut problems: Vec<String> = Vec::new();
+
+    for path in slot_overlay_files(config_dir)? {
+      if let Err(err) = apply_slot_overlay(&mut logs, &path, &mut problems) {
+        problems.push(format!("{:?}: {}", path, err));
+      }
+    }
 
-    serde_json::from_str(&s).unwrap()
+    if problems.is_empty() {
+      Ok(logs)
+    } else {
+      Err(anyhow!("Invalid slot data:\n  {}", problems.join("\n  ")))
+    }
   }
 }
 
+fn slot_overlay_files(config_dir: &Path) -> Result<Vec<PathBuf>> {
+  let mut files: Vec<PathBuf> = Vec::new();
+
+  let slots_file = config_dir.join(SLOTS_FILE);
+  if slots_file.is_file() {
+    files.push(slots_file);
+  }
+
+  let overlay_dir = config_dir.join(SLOTS_OVERLAY_DIR);
+  if overlay_dir.is_dir() {
+    let mut overlays: Vec<PathBuf> = fs::read_dir(&overlay_dir)?
+      .filter_map(|entry| entry.ok())
+      .map(|entry| entry.path())
+      .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")))
+      .collect();
+    overlays.sort();
+    files.extend(overlays);
+  }
+
+  Ok(files)
+}
+
+fn apply_slot_overlay(logs: &mut Vec<AudioLog>, path: &Path, problems: &mut Vec<String>) -> Result<()> {
+  let raw = fs::read_to_string(path)?;
+  let entries: Vec<serde_json::Map<String, serde_json::Value>> = serde_json::from_str(&raw)?;
+
+  for (i, entry) in entries.into_iter().enumerate() {
+    let Some(subtitle) = entry.get("subtitle").and_then(|subtitle| subtitle.as_str()).map(str::to_owned) else {
+      problems.push(format!("{:?}: entry {} has no `subtitle`", path, i + 1));
+      continue;
+    };
+
+    let existing = logs.iter().position(|log| log.subtitle == subtitle);
+    let mut merged = match existing {
+      Some(index) => match serde_json::to_value(&logs[index])? {
+        serde_json::Value::Object(fields) => fields,
+        _ => serde_json::Map::new(),
+      },
+      None => serde_json::Map::new(),
+    };
+    merged.extend(entry);
+
+    match serde_json::from_value::<AudioLog>(serde_json::Value::Object(merged)) {
+      Ok(log) => match existing {
+        Some(index) => logs[index] = log,
+        None => logs.push(log),
+      },
+      Err(err) => problems.push(format!("{:?}: slot `{}`: {}", path, subtitle, err)),
+    }
+  }
+
+  Ok(())
+}
+
 // ---------------------------------------------------------------------------------------------------
 // Data paths
 
@@ -49,11 +178,55 @@ fn data_bak_path(witness_dir: &Path) -> PathBuf { witness_dir.join(r"data-pc.zip
 fn data_dir_path(witness_dir: &Path) -> PathBuf { witness_dir.join(r"data-pc") }
 fn data_zip_path(witness_dir: &Path) -> PathBuf { witness_dir.join(r"data-pc.zip") }
 
-#[allow(dead_code)]
 fn tmp_dir_path(witness_dir: &Path) -> PathBuf { witness_dir.join(r"tmp") }
+fn original_logs_dir_path(witness_dir: &Path) -> PathBuf { tmp_dir_path(witness_dir).join("original_logs") }
+fn original_pkgs_dir_path(witness_dir: &Path) -> PathBuf { tmp_dir_path(witness_dir).join("original_packages") }
+
+// Paths are built from individual components rather than from strings containing separators so that
+// they resolve correctly on both Windows and Linux (e.g. Steam/Proton) installs
+
+pub fn psalm_46_path(witness_dir: &Path) -> PathBuf { witness_dir.join("data").join("videos").join("psalm46.ogg") }
+
+fn strings_dir_path(witness_dir: &Path) -> PathBuf { witness_dir.join("data").join("strings") }
+
+// The game has one subtitles file per language, named after the language code (`en.subtitles`,
+// `de.subtitles`, ...). English is the language of the `.sub` files next to custom logs; the other
+// languages use `<log>.<language>.sub` files where there are any.
+pub const DEFAULT_LANGUAGE: &str = "en";
+const SUBTITLES_EXTENSION: &str = "subtitles";
+
+fn subtitles_path_for(witness_dir: &Path, language: &str) -> PathBuf {
+  strings_dir_path(witness_dir).join(format!("{}.{}", language, SUBTITLES_EXTENSION))
+}
+fn subs_bak_path_for(witness_dir: &Path, language: &str) -> PathBuf {
+  strings_dir_path(witness_dir).join(format!("{}.{}.bak", language, SUBTITLES_EXTENSION))
+}
+
+fn subtitles_path(witness_dir: &Path) -> PathBuf { subtitles_path_for(witness_dir, DEFAULT_LANGUAGE) }
+fn subs_bak_path(witness_dir: &Path)  -> PathBuf { subs_bak_path_for(witness_dir, DEFAULT_LANGUAGE) }
 
-fn subtitles_path(witness_dir: &Path) -> PathBuf { witness_dir.join(r"data\strings\en.subtitles") }
-fn subs_bak_path(witness_dir: &Path)  -> PathBuf { witness_dir.join(r"data\strings\en.subtitles.bak") }
+// The languages the game has subtitles for, from the subtitles files and their backups. English is
+// always first.
+pub fn subtitle_languages(witness_dir: &Path) -> Result<Vec<String>> {
+  let strings_dir = strings_dir_path(witness_dir);
+  let mut languages: Vec<String> = Vec::new();
+
+  if strings_dir.is_dir() {
+    for entry in fs::read_dir(&strings_dir)? {
+      let name = entry?.file_name().to_string_lossy().to_string();
+      let name = name.strip_suffix(".bak").unwrap_or(&name);
+
+      if let Some(language) = name.strip_suffix(&format!(".{}", SUBTITLES_EXTENSION)) {
+        if !language.is_empty() && !languages.iter().any(|known| known == language) {
+          languages.push(language.to_owned());
+        }
+      }
+    }
+  }
+
+  languages.sort_by_key(|language| (language != DEFAULT_LANGUAGE, language.clone()));
+  Ok(languages)
+}
 
 pub fn witness_dir_is_okay(witness_dir: &Path) -> bool {
   let data_dir = data_dir_path(witness_dir);
@@ -80,11 +253,39 @@ pub fn data_needs_backing_up(witness_dir: &Path) -> bool {
 }
 
 pub fn subtitles_need_backing_up(witness_dir: &Path) -> bool {
-  let subs = subtitles_path(witness_dir);
-  let subs_bak = subs_bak_path(witness_dir);
+  let languages = subtitle_languages(witness_dir).unwrap_or_else(|_| vec![DEFAULT_LANGUAGE.to_owned()]);
 
-  
-  subs.exists() && !subs_bak.exists()
+  languages.iter().any(|language| {
+    subtitles_path_for(witness_dir, language).exists() && !subs_bak_path_for(witness_dir, language).exists()
+  })
+}
+
+// Older versions only backed up (and only modified) `en.subtitles`. With such a backup the English
+// file may already be randomized, so only the languages missing from the backup are backed up: those
+// versions never touched them.
+pub fn subtitles_backup_is_legacy(witness_dir: &Path) -> bool {
+  subs_bak_path(witness_dir).exists() && subtitles_need_backing_up(witness_dir)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackupState {
+  pub data_unpacked: bool,
+  pub data_backed_up: bool,
+  pub subtitles_backed_up: bool,
+}
+
+impl BackupState {
+  pub fn is_complete(&self) -> bool {
+    self.data_unpacked && self.data_backed_up && self.subtitles_backed_up
+  }
+}
+
+pub fn backup_state(witness_dir: &Path) -> BackupState {
+  BackupState {
+    data_unpacked:       !data_needs_unpacking(witness_dir),
+    data_backed_up:      !data_needs_backing_up(witness_dir),
+    subtitles_backed_up: !subtitles_need_backing_up(witness_dir),
+  }
 }
 
 pub fn unpack_witness_data(witness_dir: &Path) -> Result<()> {
@@ -117,25 +318,48 @@ pub fn restore_audio_backup(witness_dir: &Path) -> Result<()> {
 }
 
 pub fn create_subtitles_backup(witness_dir: &Path) -> Result<()> {
-  let subs = subtitles_path(witness_dir);
-  let bak  = subs_bak_path(witness_dir);
+  if subtitles_backup_is_legacy(witness_dir) {
+    log::info!("Keeping the English subtitles backup of an older version, backing up the other languages");
+  }
 
-  if !bak.exists() {
-    fs::copy(subs, bak)?;
+  for language in subtitle_languages(witness_dir)? {
+    let subs = subtitles_path_for(witness_dir, &language);
+    let bak  = subs_bak_path_for(witness_dir, &language);
+
+    // An existing backup is never replaced, since the file it was made from may be randomized by now
+    if subs.exists() && !bak.exists() {
+      fs::copy(subs, bak)?;
+    }
   }
 
   Ok(())
 }
 
 pub fn restore_subtitles_backup(witness_dir: &Path) -> Result<()> {
-  let subs = subtitles_path(witness_dir);
-  let bak  = subs_bak_path(witness_dir);
+  let bak = subs_bak_path(witness_dir);
+  if !bak.exists() {
+    return Err(anyhow!("Could not restore subtitles backup: {:?} does not exist", bak));
+  }
 
-  fs::copy(bak, subs)?;
+  for language in subtitle_languages(witness_dir)? {
+    let subs = subtitles_path_for(witness_dir, &language);
+    let bak  = subs_bak_path_for(witness_dir, &language);
+
+    if bak.exists() {
+      fs::copy(bak, subs)?;
+    }
+  }
 
   Ok(())
 }
 
+// Restores every backed up data file, so that an insertion that fails partway through doesn't leave
+// the game half randomized
+pub fn restore_backups(witness_dir: &Path) -> Result<()> {
+  restore_subtitles_backup(witness_dir)?;
+  restore_audio_backup(witness_dir)
+}
+
 #[allow(dead_code)]
 fn create_temp_dir(witness_dir: &Path) -> Result<()> {
   let tmp_dir = tmp_dir_path(witness_dir);
@@ -152,23 +376,28 @@ fn create_temp_dir(witness_dir: &Path) -> Result<()> {
 // ---------------------------------------------------------------------------------------------------
 
 pub fn load_subtitles(witness_dir: &Path) -> Result<Vec<Subtitle>> {
-  let path = subtitles_path(witness_dir);
+  load_subtitles_file(&subtitles_path(witness_dir))
+}
 
-  let raw_subs = std::fs::read_to_string(path)?;
-  let pattern = Regex::new(r"(?mR)^:").unwrap(); // (?mR) = multi-line mode + CRLF mode
-  let raw_chunks = pattern.split(&raw_subs);
-  
-  raw_chunks.filter(|chunk| !chunk.trim().is_empty()).map(|chunk| {
-    if let Some((key, val)) = chunk.split_once("\r\n") {
-      Ok(Subtitle {
-        key: key.trim().to_owned(),
-        val: val.trim().to_owned(),
-      })
-    } else {
-      log::error!("Read empty chunk in subs file: {:?}", chunk);
-      Err(anyhow!("Read empty chunk in subs file: {:?}", chunk))
-    }
-  }).collect()
+pub fn load_subtitles_for(witness_dir: &Path, language: &str) -> Result<Vec<Subtitle>> {
+  load_subtitles_file(&subtitles_path_for(witness_dir, language))
+}
+
+// The subtitles as they were before the game was first modified
+pub fn load_original_subtitles(witness_dir: &Path) -> Result<Vec<Subtitle>> {
+  load_subtitles_file(&subs_bak_path(witness_dir))
+}
+
+pub fn load_original_subtitles_for(witness_dir: &Path, language: &str) -> Result<Vec<Subtitle>> {
+  load_subtitles_file(&subs_bak_path_for(witness_dir, language))
+}
+
+fn load_subtitles_file(path: &Path) -> Result<Vec<Subtitle>> {
+  let subtitles = SubtitlesFile::read(path)?.entries().iter()
+    .map(|entry| Subtitle { key: entry.key().to_owned(), val: entry.text().to_owned() })
+    .collect();
+
+  Ok(subtitles)
 }
 
 pub fn dump_logs(witness_dir: &Path, dest_dir: &Path, logs: &Vec<AudioLog>, subs: &Vec<Subtitle>) -> Result<()> {
@@ -176,8 +405,16 @@ pub fn dump_logs(witness_dir: &Path, dest_dir: &Path, logs: &Vec<AudioLog>, subs
     .map(|Subtitle {key, val}| (key.clone(), val.clone()))
     .collect();
 
+  let missing: Vec<String> = logs.iter()
+    .filter(|log| !subs_hash.contains_key(&log.subtitle))
+    .map(|log| format!("Slot `{}` has no subtitle in the game's subtitles", log.subtitle))
+    .collect();
+  if !missing.is_empty() {
+    return Err(anyhow!("Could not dump the audio logs:\n  {}", missing.join("\n  ")));
+  }
+
   for log in logs {
-    let subtitle = subs_hash.get(&log.subtitle).unwrap();
+    let subtitle = &subs_hash[&log.subtitle];
 
     if let Some(package) = &(log.package) {
       let package_path = data_dir_path(witness_dir).join(package);
@@ -204,6 +441,82 @@ pub fn dump_logs(witness_dir: &Path, dest_dir: &Path, logs: &Vec<AudioLog>, subs
   Ok(())
 }
 
+// ---------------------------------------------------------------------------------------------------
+// Original audio logs
+
+// Extracts the game's own audio logs from the pristine data file backup into a temporary directory,
+// as `.ogg` files with their original subtitles in `.sub` files next to them (and `.<language>.sub`
+// files for the other languages), so that they can be placed like custom logs. Extracting from the
+// backup rather than from the unpacked data means the sources stay intact while logs are swapped
+// around, even if the game was already randomized.
+pub fn extract_original_logs(witness_dir: &Path) -> Result<PathBuf> {
+  let data_bak = data_bak_path(witness_dir);
+  if !data_bak.exists() {
+    return Err(anyhow!("Could not extract original audio logs: {:?} does not exist", data_bak));
+  }
+
+  let subs_hash: HashMap<String, String> = load_original_subtitles(witness_dir)?.into_iter()
+    .map(|Subtitle {key, val}| (key, val))
+    .collect();
+
+  let mut translations: Vec<(String, HashMap<String, String>)> = Vec::new();
+  for language in subtitle_languages(witness_dir)? {
+    if language != DEFAULT_LANGUAGE && subs_bak_path_for(witness_dir, &language).exists() {
+      let subs = load_original_subtitles_for(witness_dir, &language)?.into_iter()
+        .map(|Subtitle {key, val}| (key, val))
+        .collect();
+      translations.push((language, subs));
+    }
+  }
+
+  let dest_dir = original_logs_dir_path(witness_dir);
+  let pkgs_dir = original_pkgs_dir_path(witness_dir);
+  remove_original_logs(witness_dir)?;
+  fs::create_dir_all(&dest_dir)?;
+  fs::create_dir_all(&pkgs_dir)?;
+
+  for log in DataStore::get_logs()? {
+    if let Some(package) = &log.package {
+      let package_path = pkgs_dir.join(package);
+      if !package_path.exists() {
+        zip::extract(&data_bak, package, &pkgs_dir)?;
+      }
+      zip::extract(&package_path, &log.filename, &dest_dir)?;
+    } else {
+      zip::extract(&data_bak, &log.filename, &dest_dir)?;
+    }
+
+    let sound_path = dest_dir.join(&log.filename);
+    util::sound_to_ogg(&sound_path, &sound_path.with_extension("ogg"))
+      .map_err(|err| anyhow!("Could not extract original audio log {:?}: {}", log.filename, err))?;
+    fs::remove_file(&sound_path)?;
+
+    let subtitle = subs_hash.get(&log.subtitle)
+      .ok_or_else(|| anyhow!("Original subtitles are missing `{}`", log.subtitle))?;
+    fs::write(sound_path.with_extension("sub"), subtitle)?;
+
+    for (language, subs) in &translations {
+      if let Some(subtitle) = subs.get(&log.subtitle) {
+        fs::write(translated_subs_path(&sound_path, language), subtitle)?;
+      }
+    }
+  }
+
+  fs::remove_dir_all(&pkgs_dir)?;
+
+  Ok(dest_dir)
+}
+
+pub fn remove_original_logs(witness_dir: &Path) -> Result<()> {
+  for dir in [original_logs_dir_path(witness_dir), original_pkgs_dir_path(witness_dir)] {
+    if dir.exists() {
+      fs::remove_dir_all(dir)?;
+    }
+  }
+
+  Ok(())
+}
+
 // ---------------------------------------------------------------------------------------------------
 // Inserting audio files
 
@@ -236,9 +549,7 @@ fn insert_sound_loose(
   files: Vec<SoundInsertion>,
   witness_dir: &Path,
 ) -> Result<()> {
-  let mut dest_pkg_path = PathBuf::new();
-  dest_pkg_path.push(witness_dir);
-  dest_pkg_path.push("data-pc");
+  let dest_pkg_path = data_dir_path(witness_dir);
 
   for insertion in files {
     let mut file_path = dest_pkg_path.clone();
@@ -257,24 +568,13 @@ fn insert_sound_packaged(
 ) -> Result<()> {
   let dest_pkg_stem = PathBuf::from(dest_pkg.file_stem().unwrap());
 
-  let mut dest_pkg_path = PathBuf::new();
-  dest_pkg_path.push(witness_dir);
-  dest_pkg_path.push("data-pc");
-  dest_pkg_path.push(dest_pkg);
-  
-  let mut unpacked_pkg_path = PathBuf::new();
-  unpacked_pkg_path.push(witness_dir);
-  unpacked_pkg_path.push("tmp");
-  unpacked_pkg_path.push(&dest_pkg_stem);
+  let dest_pkg_path = data_dir_path(witness_dir).join(dest_pkg);
+  let unpacked_pkg_path = tmp_dir_path(witness_dir).join(&dest_pkg_stem);
   
   zip::unpack(&dest_pkg_path, &unpacked_pkg_path)?;
 
   for insertion in files {
-    let mut file_path = PathBuf::new();
-    file_path.push(witness_dir);
-    file_path.push("tmp");
-    file_path.push(&dest_pkg_stem);
-    file_path.push(insertion.dest_file);
+    let file_path = unpacked_pkg_path.join(insertion.dest_file);
     fs::remove_file(&file_path)?;
 
     util::ogg_to_sound(&insertion.source_file, &file_path)?;
@@ -287,54 +587,161 @@ fn insert_sound_packaged(
   Ok(())
 }
 
+// Everything that would make inserting these sound files fail, found without changing anything
+pub fn sound_problems(witness_dir: &Path, inserted_logs: &SoundInsertionMap) -> Vec<String> {
+  let mut problems = BTreeSet::new();
+
+  for (destination, files) in inserted_logs {
+    if let SoundDestination::Package(pkg) = destination {
+      if !data_dir_path(witness_dir).join(pkg).is_file() {
+        problems.insert(format!("Package {:?} does not exist in the game's data files", pkg));
+      }
+    }
+
+    for insertion in files {
+      let source_file = &insertion.source_file;
+      match fs::File::open(source_file).and_then(|file| file.metadata()) {
+        Err(err) => { problems.insert(format!("Could not read audio file {:?}: {}", source_file, err)); },
+        Ok(metadata) if !metadata.is_file() => { problems.insert(format!("Audio file {:?} is not a file", source_file)); },
+        Ok(metadata) if metadata.len() > u32::MAX.into() => { problems.insert(format!("Audio file {:?} is too large", source_file)); },
+        Ok(_) => {},
+      }
+    }
+  }
+
+  problems.into_iter().collect()
+}
+
 // ---------------------------------------------------------------------------------------------------
 // Inserting subtitles
 
+// Maps subtitle keys to the English `.sub` file of the log inserted in that slot, if it has one
 pub type SubsInsertionMap = HashMap<String, Option<PathBuf>>;
 
+// Where the subtitles of a log for another language are written, e.g. `foo.de.sub` for `foo.ogg`.
+// They are read from any of the subtitles file formats (see `subtitles::find_subs_file`).
+pub fn translated_subs_path(path: &Path, language: &str) -> PathBuf {
+  path.with_extension(format!("{}.sub", language))
+}
+
+// Writes the subtitles of the inserted logs into every language's subtitles file, leaving the rest
+// of each file as it was
 pub fn insert_subtitles(
   witness_dir: &Path,
-  subtitles: Vec<Subtitle>, 
   inserted_subtitles: SubsInsertionMap,
+  fallback: SubtitleFallback,
 ) -> Result<()> {
-  let subs_path = subtitles_path(&witness_dir);
-  let mut subs_file = fs::File::create(&subs_path)?;
-  
-  compile_subtitles(subtitles, inserted_subtitles, &mut subs_file)
+  // Every `.sub` file and subtitles file is read before any subtitles file is changed, so a bad one
+  // leaves them all as they were
+  for (subs_path, mut subtitles, texts) in all_inserted_texts(witness_dir, &inserted_subtitles, fallback)? {
+    for (key, text) in texts {
+      if !subtitles.set_text(&key, &text) {
+        return Err(anyhow!("Subtitle `{}` is not in {:?}", key, subs_path));
+      }
+    }
+    subtitles.write(&subs_path)?;
+  }
+
+  Ok(())
 }
 
-fn compile_subtitles<W: Write>(
-  subtitles: Vec<Subtitle>, 
-  inserted_subtitles: SubsInsertionMap,
-  writeable: &mut W
-) -> Result<()> {
-  // writeln! emits '\n' as line terminator for all platforms, so we need to explicitly add the
-  // carriage return to keep the subs file from getting mangled across multiple randomizations
-  for Subtitle { key, val } in subtitles {
-    writeln!(writeable, ": {}\r", key)?;
-    writeln!(writeable, "\r")?;
-    if inserted_subtitles.contains_key(&key) {
-      if let Some(path) = inserted_subtitles.get(&key).unwrap() {
-        let text = match std::fs::read_to_string(path) {
-          Ok(str) => str,
-          Err(err) => {
-            log::error!("Could not read subs file {:?} - {:?}", path, err);
-            String::new()
-          }
-        };
+// Every `.sub` file that would be inserted but can't be, and every subtitle key missing from a
+// language's subtitles file, without stopping at the first one
+pub fn subtitle_problems(
+  witness_dir: &Path,
+  inserted_subtitles: &SubsInsertionMap,
+  fallback: SubtitleFallback,
+) -> Result<Vec<String>> {
+  let (_, problems) = inserted_texts_and_problems(witness_dir, inserted_subtitles, fallback)?;
+  Ok(problems.into_iter().collect())
+}
+
+// The subtitles file of each language, with the texts to insert into it keyed by subtitle key
+type LanguageTexts = Vec<(PathBuf, SubtitlesFile, HashMap<String, String>)>;
+
+fn all_inserted_texts(
+  witness_dir: &Path,
+  inserted_subtitles: &SubsInsertionMap,
+  fallback: SubtitleFallback,
+) -> Result<LanguageTexts> {
+  let (all_texts, problems) = inserted_texts_and_problems(witness_dir, inserted_subtitles, fallback)?;
+
+  if !problems.is_empty() {
+    let problems: Vec<String> = problems.into_iter().collect();
+    return Err(anyhow!("Invalid subtitles files:\n  {}", problems.join("\n  ")));
+  }
+
+  Ok(all_texts)
+}
+
+// The texts to insert into each language's subtitles file, and the problems found reading them
+fn inserted_texts_and_problems(
+  witness_dir: &Path,
+  inserted_subtitles: &SubsInsertionMap,
+  fallback: SubtitleFallback,
+) -> Result<(LanguageTexts, BTreeSet<String>)> {
+  let mut problems = BTreeSet::new();
+  let mut all_texts = Vec::new();
+
+  for language in subtitle_languages(witness_dir)? {
+    let subs_path = subtitles_path_for(witness_dir, &language);
+    if !subs_path.exists() {
+      continue;
+    }
 
-        writeln!(writeable, "{}\r", text)?;
-      } else {
-        writeln!(writeable, "\r")?;
+    let subtitles = match SubtitlesFile::read(&subs_path) {
+      Ok(subtitles) => subtitles,
+      Err(err) => {
+        problems.insert(err.to_string());
+        continue;
       }
-    } else {
-      writeln!(writeable, "{}\r", val)?;
+    };
+
+    for key in inserted_subtitles.keys().filter(|key| subtitles.get(key).is_none()) {
+      problems.insert(format!("Subtitle `{}` is not in {:?}", key, subs_path));
     }
-    writeln!(writeable, "\r")?;
-    writeln!(writeable, "\r")?;
+
+    let texts = inserted_texts(inserted_subtitles, &language, fallback, &mut problems);
+    all_texts.push((subs_path, subtitles, texts));
   }
 
-  Ok(())
+  Ok((all_texts, problems))
+}
+
+// The text of each inserted log in one language. A `<log>.<language>.sub` file (or transcript) takes
+// precedence, and the English subtitles file is used for English or as the fallback.
+fn inserted_texts(
+  inserted_subtitles: &SubsInsertionMap,
+  language: &str,
+  fallback: SubtitleFallback,
+  problems: &mut BTreeSet<String>,
+) -> HashMap<String, String> {
+  let mut read = |path: &Path| match subtitles::read_sub_file(path) {
+    Ok(text) => text,
+    Err(err) => {
+      problems.insert(err.to_string());
+      String::new()
+    }
+  };
+
+  inserted_subtitles.iter()
+    .map(|(key, path)| {
+      let text = match path {
+        Some(path) => {
+          if let Some(translated) = subtitles::find_subs_file(path, Some(language)) {
+            read(&translated)
+          } else if language == DEFAULT_LANGUAGE || fallback == SubtitleFallback::English {
+            read(path)
+          } else {
+            String::new()
+          }
+        },
+        None => String::new(),
+      };
+
+      (key.clone(), text)
+    })
+    .collect()
 }
 
 // ---------------------------------------------------------------------------------------------------
@@ -347,21 +754,14 @@ pub fn insert_on_mountaintop(witness_dir: &Path, log_to_insert: &Path) -> Result
   };
   let package = PathBuf::from("save_58408_0.pkg");
 
-  insert_sound_packaged(vec!((insertion)), package, witness_dir)?;
-
-  let subs_path = log_to_insert.with_extension("sub");
-  let new_subs = if subs_path.exists() {
-    Some(subs_path)
-  } else {
-    None
-  };
+  insert_sound_packaged(vec![insertion], package, witness_dir)?;
 
-  let subs = load_subtitles(witness_dir)?;
+  let new_subs = subtitles::find_subs_file(log_to_insert, None);
 
   let mut map: SubsInsertionMap = HashMap::new();
   map.insert("schweickart_eva".to_owned(), new_subs);
 
-  insert_subtitles(witness_dir, subs, map)?;
+  insert_subtitles(witness_dir, map, SubtitleFallback::default())?;
 
   Ok(())
 }
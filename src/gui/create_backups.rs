@@ -6,7 +6,7 @@ use std::{
   thread,
 };
 
-use crate::core::{
+use witness_audio_randomizer::core::{
   witness_data,
 };
 
@@ -2,21 +2,23 @@ use nwd::NwgUi;
 use nwg::NativeUi;
 use std::{
   cell::RefCell,
-  collections::HashMap,
   path::{Path, PathBuf},
   thread,
 };
 
-use crate::core::{
+use witness_audio_randomizer::core::{
+  randomizer,
+  spoiler,
+  spoiler::SpoilerLog,
   witness_data,
-  witness_data::{AudioLog, DataStore, SoundDestination, SoundInsertion, SoundInsertionMap, SubsInsertionMap, Subtitle},
+  witness_data::{SoundDestination, Subtitle, SubtitleFallback},
 };
 
-fn secret_of_psalm_46_ogg_path(witness_dir: &Path) -> PathBuf { witness_dir.join(r"data/videos/psalm46.ogg") }
-
 #[derive(Debug, Default)]
 struct FeelingLuckyWindowParams {
   witness_dir: PathBuf,
+  subtitle_fallback: SubtitleFallback,
+  strict: bool,
 }
 
 #[derive(Default, NwgUi)]
@@ -40,12 +42,12 @@ pub struct FeelingLuckyWindow {
 }
 
 impl FeelingLuckyWindow {
-  pub fn show(witness_dir: &Path, sender: nwg::NoticeSender) {
+  pub fn show(witness_dir: &Path, subtitle_fallback: SubtitleFallback, strict: bool, sender: nwg::NoticeSender) {
     let witness_dir = witness_dir.to_owned();
 
     thread::spawn(move || {
       let params = RefCell::new(
-        FeelingLuckyWindowParams {witness_dir}
+        FeelingLuckyWindowParams {witness_dir, subtitle_fallback, strict}
       );
       let dialogue = FeelingLuckyWindow { params, ..Default::default() };
       let _ui = FeelingLuckyWindow::build_ui(dialogue).expect("Failed to build UI");
@@ -59,7 +61,6 @@ impl FeelingLuckyWindow {
   fn run(&self) {
     let params = self.params.borrow();
 
-    let logs_data = DataStore::get_logs();
     let subs_data = match witness_data::load_subtitles(&params.witness_dir) {
       Ok(data) => data,
       Err(err) => {
@@ -70,7 +71,7 @@ impl FeelingLuckyWindow {
       }
     };
 
-    let secret_of_psalm_46_path = secret_of_psalm_46_ogg_path(&params.witness_dir);
+    let secret_of_psalm_46_path = witness_data::psalm_46_path(&params.witness_dir);
     let secret_of_psalm_46_subs = subs_data.iter()
       .find(|Subtitle {key, val: _val}| key == "psalm46")
       .unwrap_or(&Subtitle {key: String::from(""), val: String::from("")})
@@ -86,29 +87,23 @@ impl FeelingLuckyWindow {
       return;
     }
 
-    let mut logs: SoundInsertionMap = HashMap::new();
-    let mut subs: SubsInsertionMap = HashMap::new();
-
-    let mut error_count = 0;
-
-    for AudioLog {package, filename, subtitle} in logs_data {
-      let dest_pkg = if let Some(path) = package {
-        SoundDestination::Package(path)
-      } else {
-        SoundDestination::Root
-      };
-
-      let insertion = SoundInsertion { source_file: secret_of_psalm_46_path.clone(), dest_file: filename };
-
-      if logs.contains_key(&dest_pkg) {
-        let d: &mut Vec<SoundInsertion> = logs.get_mut(&dest_pkg).unwrap();
-        d.push(insertion); 
-      } else {
-        logs.insert(dest_pkg, vec![insertion]);
+    let placements = randomizer::feeling_lucky(&secret_of_psalm_46_path, &subs_file).and_then(|placements| {
+      randomizer::check_insertion(&params.witness_dir, &placements, params.subtitle_fallback, params.strict)?;
+      Ok(placements)
+    });
+    let placements = match placements {
+      Ok(placements) => placements,
+      Err(err) => {
+        log::error!("Error placing audio logs: {:?}", err);
+        let _ = std::fs::remove_file(&subs_file);
+        self.progress_text.set_text("Failure - see logs for more details");
+        self.close_button.set_enabled(true);
+        return;
       }
+    };
+    let (logs, subs) = randomizer::insertion_maps(&placements);
 
-      subs.insert(subtitle, Some(subs_file.clone()));
-    }
+    let mut error_count = 0;
 
     let progress_bar_range = 0..((logs.len() + 1) as u32);
     println!("Range: {:?}", progress_bar_range);
@@ -132,13 +127,24 @@ impl FeelingLuckyWindow {
       if result.is_err() {
         log::error!("Sound insertion failed: {:?}", result);
         error_count += 1;
+
+        if params.strict {
+          break;
+        }
       }
 
       self.progress_bar.advance();
     }
 
+    if params.strict && error_count > 0 {
+      let _ = std::fs::remove_file(&subs_file);
+      self.roll_back(&params.witness_dir);
+      self.close_button.set_enabled(true);
+      return;
+    }
+
     self.progress_text.set_text("Updating subtitles");
-    match witness_data::insert_subtitles(&params.witness_dir, subs_data, subs) {
+    match witness_data::insert_subtitles(&params.witness_dir, subs, params.subtitle_fallback) {
       Ok(()) => {},
       Err(err) => {
         log::error!("Subtitles insertion failed: {:?}", err);
@@ -148,6 +154,17 @@ impl FeelingLuckyWindow {
     self.progress_bar.advance();
 
     let _ = std::fs::remove_file(&subs_file);
+
+    if params.strict && error_count > 0 {
+      self.roll_back(&params.witness_dir);
+      self.close_button.set_enabled(true);
+      return;
+    }
+
+    let spoiler = SpoilerLog::new(None, &placements);
+    if let Err(err) = spoiler::write_spoiler(&params.witness_dir, &spoiler) {
+      log::error!("Could not write spoiler log: {:?}", err);
+    }
     
     if error_count == 0 {
       self.progress_text.set_text("Finished successfully");
@@ -157,6 +174,23 @@ impl FeelingLuckyWindow {
     self.close_button.set_enabled(true);
   }
 
+  // A strict run that fails partway through puts back the backed up data files, so the game is not
+  // left half randomized
+  fn roll_back(&self, witness_dir: &Path) {
+    self.progress_text.set_text("Failure - restoring the data files from the backup");
+
+    let restored = witness_data::restore_backups(witness_dir)
+      .and_then(|()| spoiler::remove_spoiler(witness_dir));
+
+    match restored {
+      Ok(()) => self.progress_text.set_text("Failure - the data files were restored, see logs for more details"),
+      Err(err) => {
+        log::error!("Could not restore the data files: {:?}", err);
+        self.progress_text.set_text("Failure - restore the backups before playing, see logs for more details");
+      },
+    }
+  }
+
   fn close(&self) {
     nwg::stop_thread_dispatch();
   }
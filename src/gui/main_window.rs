@@ -6,13 +6,11 @@ use rand::{
 };
 use std::{
   cell::RefCell,
-  collections::hash_map::DefaultHasher,
   ffi::OsString,
-  hash::Hasher,
   path::PathBuf,
 };
 
-use crate::core::{
+use witness_audio_randomizer::core::{
   config::Config,
   witness_data,
   witness_data::DataStore,
@@ -22,7 +20,7 @@ use super::{
   create_backups::CreateBackupsDialogue,
   feeling_lucky::FeelingLuckyWindow,
   message_box::MessageBox,
-  randomizer::RandomizerWindow,
+  randomizer::{RandomizerWindow, RandomizerWindowParams},
   restore_backups::RestoreBackupsDialogue,
 };
 
@@ -86,6 +84,22 @@ pub struct RandoGui {
   // ---------------------------
   // Row 2
 
+  #[nwg_control( text: "Stop on errors" )]
+  #[nwg_layout_item(layout: grid, col: 0, row: 2, col_span: 4)]
+  strict_check_box: nwg::CheckBox,
+
+  #[nwg_control( text: "Shuffle the game's own logs instead" )]
+  #[nwg_layout_item(layout: grid, col: 4, row: 2, col_span: 10)]
+  vanilla_check_box: nwg::CheckBox,
+
+  #[nwg_control( text: "Custom logs:" )]
+  #[nwg_layout_item(layout: grid, col: 14, row: 2, col_span: 3)]
+  mix_label: nwg::Label,
+
+  #[nwg_control( text: "", placeholder_text: Some("all") )]
+  #[nwg_layout_item(layout: grid, col: 17, row: 2, col_span: 3)]
+  mix_input: nwg::TextInput,
+
 
 
   // ---------------------------
@@ -113,6 +127,10 @@ pub struct RandoGui {
   // ---------------------------
   // Row 4
 
+  #[nwg_control( text: "Fill all slots" )]
+  #[nwg_layout_item(layout: grid, col: 0, row: 4, col_span: 6)]
+  fill_check_box: nwg::CheckBox,
+
   #[nwg_control( text: "I'm feeling lucky", enabled: false )]
   #[nwg_layout_item(layout: grid, col: 7, row: 4, col_span: 4)]
   #[nwg_events( OnButtonClick: [RandoGui::click_feeling_lucky_button])]
@@ -153,7 +171,9 @@ impl RandoGui {
   fn on_window_close(&self) {
     let witness_dir = PathBuf::from( self.witness_dir_input.text() );
     let logs_dir = PathBuf::from( self.logs_dir_input.text() );
-    let config = Config { witness_dir, logs_dir };
+    let installs = self.config.borrow().installs.clone();
+    let subtitle_fallback = self.config.borrow().subtitle_fallback;
+    let config = Config { witness_dir, logs_dir, installs, subtitle_fallback };
 
     let _ = config.save();
 
@@ -252,7 +272,14 @@ impl RandoGui {
 
     self.dialogue_opened();
 
-    let logs = DataStore::get_logs();
+    let logs = match DataStore::get_logs() {
+      Ok(val) => val,
+      Err(err) => {
+        log::error!("Error loading slot data: {:?}", err);
+        MessageBox::show("Could not load the audio log slot data. See logs for more details.", self.dialogue_notice.sender());
+        return
+      }
+    };
     let subs = match witness_data::load_subtitles(&config.witness_dir) {
       Ok(val) => val,
       Err(err) => {
@@ -282,16 +309,19 @@ impl RandoGui {
   }
 
   fn click_randomize_button(&self) {
-    let witness_dir = PathBuf::from( self.witness_dir_input.text() );
-    let source_dir = PathBuf::from( self.logs_dir_input.text() );
-    
-    let mut hasher = DefaultHasher::new();
-    let seed_string = self.seed_input.text();
-    let seed_bytes = seed_string.as_bytes();
-    hasher.write(&seed_bytes);
-    let seed = hasher.finish();
+    let params = RandomizerWindowParams {
+      witness_dir: PathBuf::from( self.witness_dir_input.text() ),
+      source_dir: PathBuf::from( self.logs_dir_input.text() ),
+
+      seed: self.seed_input.text(),
+      fill: self.fill_check_box.check_state() == nwg::CheckBoxState::Checked,
+      vanilla: self.vanilla_check_box.check_state() == nwg::CheckBoxState::Checked,
+      mix: self.mix_input.text(),
+      subtitle_fallback: self.config.borrow().subtitle_fallback,
+      strict: self.strict_check_box.check_state() == nwg::CheckBoxState::Checked,
+    };
 
-    RandomizerWindow::show(&source_dir, &witness_dir, seed, self.dialogue_notice.sender());
+    RandomizerWindow::show(params, self.dialogue_notice.sender());
 
     self.dialogue_opened();
   }
@@ -299,7 +329,10 @@ impl RandoGui {
   fn click_feeling_lucky_button(&self) {
     let witness_dir = PathBuf::from( self.witness_dir_input.text() );
 
-    FeelingLuckyWindow::show(&witness_dir, self.dialogue_notice.sender());
+    let subtitle_fallback = self.config.borrow().subtitle_fallback;
+    let strict = self.strict_check_box.check_state() == nwg::CheckBoxState::Checked;
+
+    FeelingLuckyWindow::show(&witness_dir, subtitle_fallback, strict, self.dialogue_notice.sender());
   }
 
   fn fill_input_from_dir_picker(&self, text_box: &nwg::TextInput, initial_dir: Option<&str>) {
@@ -6,19 +6,29 @@ use std::{
   thread,
 };
 
-use crate::core::{
+use witness_audio_randomizer::core::{
+  constraints::Constraints,
   randomizer,
+  randomizer::CustomShare,
+  seed,
+  spoiler,
+  spoiler::SpoilerLog,
   witness_data,
-  witness_data::SoundDestination,
+  witness_data::{SoundDestination, SubtitleFallback},
 };
 // ---------------------------------------------------------------------------------------------------
 
 #[derive(Debug, Default)]
-struct RandomizerWindowParams {
-  seed: u64,
+pub struct RandomizerWindowParams {
+  pub seed: String,
+  pub fill: bool,
+  pub vanilla: bool,
+  pub mix: String,
+  pub subtitle_fallback: SubtitleFallback,
+  pub strict: bool,
   
-  source_dir: PathBuf,
-  witness_dir: PathBuf,
+  pub source_dir: PathBuf,
+  pub witness_dir: PathBuf,
 }
 
 #[derive(Default, NwgUi)]
@@ -42,14 +52,9 @@ pub struct RandomizerWindow {
 }
 
 impl RandomizerWindow {
-  pub fn show(source_dir: &Path, witness_dir: &Path, seed: u64, sender: nwg::NoticeSender) {
-    let source_dir = source_dir.to_owned();
-    let witness_dir = witness_dir.to_owned();
-
+  pub fn show(params: RandomizerWindowParams, sender: nwg::NoticeSender) {
     thread::spawn(move || {
-      let params = RefCell::new(
-        RandomizerWindowParams {source_dir, witness_dir, seed}
-      );
+      let params = RefCell::new(params);
       let dialogue = RandomizerWindow { params, ..Default::default() };
       let _ui = RandomizerWindow::build_ui(dialogue).expect("Failed to build UI");
       nwg::dispatch_thread_events();
@@ -62,10 +67,41 @@ impl RandomizerWindow {
   fn run(&self) {
     let params = self.params.borrow();
 
-    let subs_data = match witness_data::load_subtitles(&params.witness_dir) {
-      Ok(data) => data,
+    // An empty share means no original logs are mixed in
+    let custom_share = match params.mix.trim() {
+      "" => Ok(None),
+      mix => mix.parse::<CustomShare>().map(Some),
+    };
+    let uses_originals = params.vanilla || !params.mix.trim().is_empty();
+
+    let placements = custom_share.and_then(|custom_share| {
+      let originals_dir = if uses_originals {
+        self.progress_text.set_text("Extracting original audio logs");
+        Some(witness_data::extract_original_logs(&params.witness_dir)?)
+      } else {
+        None
+      };
+      let (source_dir, originals_dir) = match originals_dir {
+        Some(originals_dir) if params.vanilla => (originals_dir, None),
+        originals_dir => (params.source_dir.clone(), originals_dir),
+      };
+
+      let options = randomizer::RandomizerOptions {
+        constraints: Constraints::for_logs_dir(&source_dir)?,
+        fill: params.fill,
+        originals_dir,
+        custom_share: custom_share.unwrap_or_default(),
+        ..Default::default()
+      };
+      let placements = randomizer::place(seed::seed_from_str(&params.seed), &source_dir, &options)?;
+      randomizer::check_insertion(&params.witness_dir, &placements, params.subtitle_fallback, params.strict)?;
+      Ok(placements)
+    });
+    let placements = match placements {
+      Ok(placements) => placements,
       Err(err) => {
-        log::error!("Error loading subtitles file: {:?}", err);
+        log::error!("Error placing audio logs: {:?}", err);
+        let _ = witness_data::remove_original_logs(&params.witness_dir);
         self.progress_text.set_text("Failure - see logs for more details");
         self.close_button.set_enabled(true);
         return;
@@ -74,7 +110,7 @@ impl RandomizerWindow {
 
     let mut error_count = 0;
     
-    let (logs, subs) = randomizer::randomize(params.seed, &params.source_dir);
+    let (logs, subs) = randomizer::insertion_maps(&placements);
 
     let progress_bar_range = 0..((logs.len() + 1) as u32);
     println!("Range: {:?}", progress_bar_range);
@@ -98,13 +134,26 @@ impl RandomizerWindow {
       if result.is_err() {
         log::error!("Sound insertion failed: {:?}", result);
         error_count += 1;
+
+        if params.strict {
+          break;
+        }
       }
 
       self.progress_bar.advance();
     }
 
+    if params.strict && error_count > 0 {
+      if uses_originals {
+        let _ = witness_data::remove_original_logs(&params.witness_dir);
+      }
+      self.roll_back(&params.witness_dir);
+      self.close_button.set_enabled(true);
+      return;
+    }
+
     self.progress_text.set_text("Updating subtitles");
-    match witness_data::insert_subtitles(&params.witness_dir, subs_data, subs) {
+    match witness_data::insert_subtitles(&params.witness_dir, subs, params.subtitle_fallback) {
       Ok(()) => {},
       Err(err) => {
         log::error!("Subtitles insertion failed: {:?}", err);
@@ -112,6 +161,23 @@ impl RandomizerWindow {
       },
     };
     self.progress_bar.advance();
+
+    if uses_originals {
+      if let Err(err) = witness_data::remove_original_logs(&params.witness_dir) {
+        log::error!("Could not remove extracted audio logs: {:?}", err);
+      }
+    }
+
+    if params.strict && error_count > 0 {
+      self.roll_back(&params.witness_dir);
+      self.close_button.set_enabled(true);
+      return;
+    }
+
+    let spoiler = SpoilerLog::new(Some(&params.seed), &placements);
+    if let Err(err) = spoiler::write_spoiler(&params.witness_dir, &spoiler) {
+      log::error!("Could not write spoiler log: {:?}", err);
+    }
     
     if error_count == 0 {
       self.progress_text.set_text("Finished successfully");
@@ -121,6 +187,23 @@ impl RandomizerWindow {
     self.close_button.set_enabled(true);
   }
 
+  // A strict run that fails partway through puts back the backed up data files, so the game is not
+  // left half randomized
+  fn roll_back(&self, witness_dir: &Path) {
+    self.progress_text.set_text("Failure - restoring the data files from the backup");
+
+    let restored = witness_data::restore_backups(witness_dir)
+      .and_then(|()| spoiler::remove_spoiler(witness_dir));
+
+    match restored {
+      Ok(()) => self.progress_text.set_text("Failure - the data files were restored, see logs for more details"),
+      Err(err) => {
+        log::error!("Could not restore the data files: {:?}", err);
+        self.progress_text.set_text("Failure - restore the backups before playing, see logs for more details");
+      },
+    }
+  }
+
   fn close(&self) {
     nwg::stop_thread_dispatch();
   }
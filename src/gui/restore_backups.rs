@@ -6,7 +6,10 @@ use std::{
   thread,
 };
 
-use crate::core::witness_data;
+use witness_audio_randomizer::core::{
+  spoiler,
+  witness_data,
+};
 
 #[derive(Debug, Default)]
 struct RestoreBackupsParams {
@@ -73,6 +76,11 @@ impl RestoreBackupsDialogue {
     };
     self.progress_bar.advance_delta(8);
 
+    if let Err(err) = spoiler::remove_spoiler(&params.witness_dir) {
+      log::error!("Failure removing spoiler log: {:?}", err);
+      success = false;
+    }
+
     if success {
       self.progress_text.set_text("Data files restored successfully");
     } else {
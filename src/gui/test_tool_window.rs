@@ -6,7 +6,7 @@ use std::{
   path::PathBuf,
 };
 
-use crate::core::{
+use witness_audio_randomizer::core::{
   config::Config,
   witness_data,
 };
@@ -107,7 +107,12 @@ impl TestToolGui {
     let old_config = self.config.borrow();
     let witness_dir = PathBuf::from( self.witness_dir_input.text() );
     
-    let config = Config { witness_dir, logs_dir: old_config.logs_dir.clone() };
+    let config = Config {
+      witness_dir,
+      logs_dir: old_config.logs_dir.clone(),
+      installs: old_config.installs.clone(),
+      subtitle_fallback: old_config.subtitle_fallback,
+    };
 
     let _ = config.save();
 
@@ -8,8 +8,7 @@ use log;
 use simplelog;
 use std::fs;
 
-mod core;
-use crate::core::config::Config;
+use witness_audio_randomizer::core::config::Config;
 
 mod gui;
 use gui::TestToolGui;
@@ -0,0 +1,48 @@
+use clap::Parser;
+use std::{
+  fs,
+  process::ExitCode,
+};
+
+use witness_audio_randomizer::core::config::Config;
+
+mod cli;
+use cli::Cli;
+
+// ---------------------------------------------------------------------------------------------------
+
+fn main() -> ExitCode {
+  let args = Cli::parse();
+
+  let log_file = fs::OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open("app.log");
+  let mut loggers: Vec<Box<dyn simplelog::SharedLogger>> = vec![
+    simplelog::TermLogger::new(
+      simplelog::LevelFilter::Warn,
+      simplelog::Config::default(),
+      simplelog::TerminalMode::Stderr,
+      simplelog::ColorChoice::Auto,
+    ),
+  ];
+  if let Ok(file) = log_file {
+    loggers.push(
+      simplelog::WriteLogger::new(simplelog::LevelFilter::Info, simplelog::Config::default(), file)
+    );
+  }
+  let _ = simplelog::CombinedLogger::init(loggers);
+
+  log::info!("Command line tool started!");
+
+  let config = Config::get();
+
+  match cli::run(args, config) {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(err) => {
+      eprintln!("Error: {:#}", err);
+      log::info!("Command failed: {:?}", err);
+      ExitCode::FAILURE
+    }
+  }
+}
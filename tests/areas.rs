@@ -0,0 +1,130 @@
+use std::{
+  collections::HashMap,
+  fs,
+};
+
+use witness_audio_randomizer::core::{
+  randomizer::{self, AreaRules, Placement, RandomizerOptions},
+  witness_data::{AudioLog, DataStore},
+};
+
+mod common;
+use common::TempDir;
+
+const AREAS: [&str; 5] = ["Town", "Mountain", "Desert", "Jungle", "Swamp"];
+
+fn with_areas(areas: AreaRules) -> RandomizerOptions {
+  RandomizerOptions { areas, ..Default::default() }
+}
+
+// The game's slots, spread over `AREAS` in turn
+fn slots_in_areas() -> Vec<AudioLog> {
+  let mut slots = DataStore::embedded_logs().unwrap();
+  for (i, slot) in slots.iter_mut().enumerate() {
+    slot.area = Some(AREAS[i % AREAS.len()].to_owned());
+  }
+
+  slots
+}
+
+fn packs_dir(name: &str) -> TempDir {
+  let dir = TempDir::new(name);
+  for pack in ["first", "second"] {
+    fs::create_dir(dir.join(pack)).unwrap();
+    for i in 0..10 {
+      fs::write(dir.join(pack).join(format!("log_{:02}.ogg", i)), b"OggS").unwrap();
+    }
+  }
+
+  dir
+}
+
+fn assert_spread(placements: &[Placement]) {
+  for pack in ["first", "second"] {
+    let in_pack: Vec<Placement> = placements.iter()
+      .filter(|placement| placement.pack.as_deref() == Some(pack))
+      .cloned()
+      .collect();
+
+    assert_eq!(in_pack.len(), 10);
+    assert!(per_area(&in_pack).len() == AREAS.len() && per_area(&in_pack).values().all(|count| *count == 2));
+  }
+}
+
+fn per_area(placements: &[Placement]) -> HashMap<String, usize> {
+  let mut counts: HashMap<String, usize> = HashMap::new();
+  for placement in placements {
+    *counts.entry(placement.slot.area.clone().unwrap()).or_default() += 1;
+  }
+
+  counts
+}
+
+#[test]
+fn only_listed_areas_are_randomized() {
+  let dir = common::logs_dir("areas_only", 60);
+
+  let options = with_areas(AreaRules { only: Some(vec!["mountain".to_owned(), "Town".to_owned()]), ..Default::default() });
+  let placements = randomizer::place_in_slots(1, slots_in_areas(), &dir, &options).unwrap();
+  assert_eq!(placements.len(), 20);
+  assert!(placements.iter().all(|placement| matches!(placement.slot.area.as_deref(), Some("Mountain" | "Town"))));
+
+  let unknown = with_areas(AreaRules { only: Some(vec!["Atlantis".to_owned()]), ..Default::default() });
+  let err = randomizer::place_in_slots(1, slots_in_areas(), &dir, &unknown).unwrap_err().to_string();
+  assert!(err.contains("Unknown area(s): Atlantis"));
+}
+
+#[test]
+fn areas_are_capped() {
+  let dir = common::logs_dir("areas_capped", 80);
+
+  for seed in [1, 2, 3] {
+    let options = with_areas(AreaRules { max_per_area: Some(3), ..Default::default() });
+    let placements = randomizer::place_in_slots(seed, slots_in_areas(), &dir, &options).unwrap();
+
+    assert_eq!(placements.len(), 3 * AREAS.len());
+    assert!(per_area(&placements).values().all(|count| *count == 3));
+
+    let none = with_areas(AreaRules { max_per_area: Some(0), ..Default::default() });
+    assert!(randomizer::place_in_slots(seed, slots_in_areas(), &dir, &none).unwrap().is_empty());
+  }
+}
+
+#[test]
+fn packs_are_spread_across_areas() {
+  let dir = packs_dir("areas_spread");
+
+  for seed in [1, 2, 3] {
+    let options = with_areas(AreaRules { spread_packs: true, ..Default::default() });
+    let placements = randomizer::place_in_slots(seed, slots_in_areas(), &dir, &options).unwrap();
+    assert_eq!(placements.len(), 20);
+    assert_spread(&placements);
+  }
+}
+
+#[test]
+fn slots_without_area_are_filled_last_when_spreading() {
+  let dir = packs_dir("areas_spread_unknown");
+
+  // Half of the slots have no area, leaving 5 in each area
+  let mut slots = slots_in_areas();
+  for slot in slots.iter_mut().skip(1).step_by(2) {
+    slot.area = None;
+  }
+
+  for seed in [1, 2, 3] {
+    let options = with_areas(AreaRules { spread_packs: true, ..Default::default() });
+    let placements = randomizer::place_in_slots(seed, slots.clone(), &dir, &options).unwrap();
+    assert!(placements.iter().all(|placement| placement.slot.area.is_some()));
+    assert_spread(&placements);
+  }
+}
+
+#[test]
+fn area_rules_need_slots_with_areas() {
+  let dir = common::logs_dir("areas_missing", 10);
+
+  let options = with_areas(AreaRules { max_per_area: Some(2), ..Default::default() });
+  let err = randomizer::place(1, &dir, &options).unwrap_err().to_string();
+  assert!(err.contains("No slot has an area"));
+}
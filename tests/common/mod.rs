@@ -0,0 +1,52 @@
+// Helpers shared by the integration tests. Not every test file uses all of them.
+#![allow(dead_code)]
+
+use std::{
+  fs,
+  ops::Deref,
+  path::{Path, PathBuf},
+};
+
+// A fresh directory in the system temp directory, deleted when it goes out of scope so that it is
+// cleaned up even when an assertion fails
+pub struct TempDir {
+  path: PathBuf,
+}
+
+impl TempDir {
+  // The name only has to be unique within a test file, since the process id is added to it
+  pub fn new(name: &str) -> TempDir {
+    let path = std::env::temp_dir().join(format!("witness_rando_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+
+    TempDir { path }
+  }
+}
+
+impl Deref for TempDir {
+  type Target = Path;
+
+  fn deref(&self) -> &Path {
+    &self.path
+  }
+}
+
+impl Drop for TempDir {
+  fn drop(&mut self) {
+    let _ = fs::remove_dir_all(&self.path);
+  }
+}
+
+// A logs directory with `count` logs named `log_00.ogg`, `log_01.ogg`, ...
+pub fn logs_dir(name: &str, count: usize) -> TempDir {
+  let dir = TempDir::new(name);
+
+  // Created in reverse order so that the directory listing order differs from the sorted order on
+  // filesystems that list files in creation order
+  for i in (0..count).rev() {
+    fs::write(dir.join(format!("log_{:02}.ogg", i)), b"OggS").unwrap();
+  }
+
+  dir
+}
@@ -0,0 +1,94 @@
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::Path,
+};
+
+use witness_audio_randomizer::core::{
+  constraints::Constraints,
+  randomizer::{self, RandomizerOptions},
+  seed,
+};
+
+mod common;
+
+fn options(raw: &str) -> RandomizerOptions {
+  RandomizerOptions { constraints: serde_json::from_str(raw).unwrap(), ..Default::default() }
+}
+
+fn placed(dir: &Path, seed_string: &str, options: &RandomizerOptions) -> BTreeMap<String, String> {
+  randomizer::place(seed::seed_from_str(seed_string), dir, options)
+    .unwrap()
+    .into_iter()
+    .map(|placement| (
+      placement.slot.subtitle,
+      placement.audio.file_stem().unwrap().to_string_lossy().to_string(),
+    ))
+    .collect()
+}
+
+#[test]
+fn constraints_are_honoured() {
+  let dir = common::logs_dir("constraints_honoured", 60);
+  let options = options(r#"{
+    "pin": { "schweickart_eva": "log_07.ogg", "tagore_end": "log_08" },
+    "keep_original": ["feynman_wine"],
+    "exclude_logs": ["log_00", "log_01.ogg"]
+  }"#);
+
+  for seed_string in ["1", "2", "constrained"] {
+    let placements = placed(&dir, seed_string, &options);
+
+    assert_eq!(placements.len(), 49);
+    assert_eq!(placements["schweickart_eva"], "log_07");
+    assert_eq!(placements["tagore_end"], "log_08");
+    assert!(!placements.contains_key("feynman_wine"));
+    assert_eq!(placements.values().filter(|log| *log == "log_07" || *log == "log_08").count(), 2);
+    assert!(!placements.values().any(|log| log == "log_00" || log == "log_01"));
+  }
+}
+
+#[test]
+fn logs_are_named_by_relative_path() {
+  let dir = common::logs_dir("constraints_paths", 60);
+  for folder in ["first", "second"] {
+    fs::create_dir(dir.join(folder)).unwrap();
+  }
+  fs::write(dir.join("first").join("intro.OGG"), b"OggS").unwrap();
+  fs::write(dir.join("second").join("intro.oga"), b"OggS").unwrap();
+  let options = options(r#"{
+    "pin": { "schweickart_eva": "second/intro.ogg" },
+    "exclude_logs": ["first/intro.ogg"]
+  }"#);
+
+  for seed_string in ["1", "2", "paths"] {
+    let placements = randomizer::place(seed::seed_from_str(seed_string), &dir, &options).unwrap();
+    let eva = placements.iter().find(|placement| placement.slot.subtitle == "schweickart_eva").unwrap();
+
+    assert_eq!(eva.audio, dir.join("second").join("intro.oga"));
+    assert!(!placements.iter().any(|placement| placement.audio == dir.join("first").join("intro.OGG")));
+  }
+}
+
+#[test]
+fn unsatisfiable_constraints_are_reported() {
+  let dir = common::logs_dir("constraints_unsatisfiable", 5);
+  let options = options(r#"{
+    "pin": { "not_a_slot": "log_01", "feynman_wine": "log_02", "tagore_end": "missing" },
+    "keep_original": ["feynman_wine"],
+    "exclude_logs": ["log_02"]
+  }"#);
+
+  let err = randomizer::place(1, &dir, &options).unwrap_err().to_string();
+
+  assert!(err.contains("unknown slot `not_a_slot`"));
+  assert!(err.contains("`missing` was not found"));
+  assert!(err.contains("Slot `feynman_wine` is both pinned and kept original"));
+  assert!(err.contains("Log `log_02` is both pinned and excluded"));
+}
+
+#[test]
+fn constraints_file_rejects_unknown_fields() {
+  assert!(serde_json::from_str::<Constraints>(r#"{"pins": {}}"#).is_err());
+  assert!(serde_json::from_str::<Constraints>(r#"{"exclude_slots": ["tagore_end"]}"#).unwrap().keep_original.len() == 1);
+}
@@ -0,0 +1,98 @@
+use std::path::PathBuf;
+
+use witness_audio_randomizer::core::discovery::{
+  parse_app_manifest, parse_epic_item, parse_gog_game_info, parse_heroic_gog_installed,
+  parse_legendary_installed, parse_library_folders, parse_vdf, VdfValue, WITNESS_STEAM_APP_ID, WITNESS_TITLE,
+};
+
+const LIBRARY_FOLDERS: &str = include_str!("fixtures/steam/libraryfolders.vdf");
+const LIBRARY_FOLDERS_LEGACY: &str = include_str!("fixtures/steam/libraryfolders_legacy.vdf");
+const APP_MANIFEST: &str = include_str!("fixtures/steam/appmanifest_210970.acf");
+const EPIC_ITEM: &str = include_str!("fixtures/epic/witness.item");
+const GOG_GAME_INFO: &str = include_str!("fixtures/gog/goggame-1333059734.info");
+const HEROIC_GOG_INSTALLED: &str = include_str!("fixtures/gog/heroic_installed.json");
+
+#[test]
+fn parses_library_folders() {
+  let libraries = parse_library_folders(LIBRARY_FOLDERS).unwrap();
+
+  assert_eq!(libraries.len(), 2);
+  assert_eq!(libraries[0].path, PathBuf::from(r"C:\Program Files (x86)\Steam"));
+  assert_eq!(libraries[0].apps, vec!["228980", "250820"]);
+  assert_eq!(libraries[1].path, PathBuf::from(r"D:\SteamLibrary"));
+  assert_eq!(libraries[1].apps, vec![WITNESS_STEAM_APP_ID]);
+}
+
+#[test]
+fn parses_legacy_library_folders() {
+  let libraries = parse_library_folders(LIBRARY_FOLDERS_LEGACY).unwrap();
+
+  let paths: Vec<PathBuf> = libraries.into_iter().map(|library| library.path).collect();
+  assert_eq!(paths, vec![
+    PathBuf::from("/mnt/games/SteamLibrary"),
+    PathBuf::from("/home/player/Games/Steam Library"),
+  ]);
+}
+
+#[test]
+fn parses_app_manifest() {
+  let manifest = parse_app_manifest(APP_MANIFEST).unwrap();
+
+  assert_eq!(manifest.app_id, WITNESS_STEAM_APP_ID);
+  assert_eq!(manifest.name, "The Witness");
+  assert_eq!(manifest.install_dir, "The Witness");
+}
+
+#[test]
+fn parses_crlf_and_comments() {
+  let raw = "// written by hand\r\n\"AppState\"\r\n{\r\n\t\"appid\"\t\"210970\" // trailing\r\n\t\"installdir\" \"The Witness\"\r\n}\r\n";
+  let manifest = parse_app_manifest(raw).unwrap();
+
+  assert_eq!(manifest.install_dir, "The Witness");
+  assert_eq!(manifest.name, "");
+}
+
+#[test]
+fn vdf_keys_are_case_insensitive() {
+  let root = parse_vdf(APP_MANIFEST).unwrap();
+  let state = root.get("appstate").unwrap();
+
+  assert_eq!(state.get("InstallDir").and_then(VdfValue::as_str), Some("The Witness"));
+}
+
+#[test]
+fn rejects_malformed_files() {
+  assert!(parse_vdf("\"AppState\"\n{\n\t\"appid\"\t\"210970\"\n").is_err());
+  assert!(parse_vdf("\"AppState\"\n{\n\t\"appid\n}\n").is_err());
+  assert!(parse_vdf("}").is_err());
+  assert!(parse_app_manifest("\"AppState\"\n{\n\t\"appid\"\t\"210970\"\n}\n").is_err());
+  assert!(parse_library_folders("\"something\"\n{\n}\n").is_err());
+}
+
+#[test]
+fn parses_epic_item() {
+  let item = parse_epic_item(EPIC_ITEM).unwrap();
+
+  assert_eq!(item.display_name, WITNESS_TITLE);
+  assert_eq!(item.install_location, PathBuf::from(r"C:\Program Files\Epic Games\TheWitness"));
+}
+
+#[test]
+fn parses_legendary_installed() {
+  let raw = r#"{"Jaguar": {"app_name": "Jaguar", "title": "The Witness", "install_path": "/games/TheWitness"}}"#;
+  let games = parse_legendary_installed(raw).unwrap();
+
+  assert_eq!(games.len(), 1);
+  assert_eq!(games[0].title, WITNESS_TITLE);
+  assert_eq!(games[0].install_path, PathBuf::from("/games/TheWitness"));
+}
+
+#[test]
+fn parses_gog_metadata() {
+  let info = parse_gog_game_info(GOG_GAME_INFO).unwrap();
+  assert_eq!(info.name, WITNESS_TITLE);
+  assert_eq!(info.game_id, "1333059734");
+
+  let installed = parse_heroic_gog_installed(HEROIC_GOG_INSTALLED).unwrap();
+  assert_eq!(installed, vec![PathBuf::from("/home/player/Games/Heroic/The Witness")]);
+}
@@ -0,0 +1,51 @@
+use std::{
+  collections::BTreeMap,
+  path::PathBuf,
+};
+
+use witness_audio_randomizer::core::{
+  randomizer::{self, RandomizerOptions},
+  witness_data::DataStore,
+};
+
+mod common;
+
+#[test]
+fn fill_replaces_every_slot_evenly() {
+  let dir = common::logs_dir("fill_even", 7);
+  let slot_count = DataStore::get_logs().unwrap().len();
+  let options = RandomizerOptions { fill: true, ..Default::default() };
+
+  for seed in [1, 2, 0xC0FFEE] {
+    let placements = randomizer::place(seed, &dir, &options).unwrap();
+    assert_eq!(placements.len(), slot_count);
+
+    let mut uses: BTreeMap<PathBuf, usize> = BTreeMap::new();
+    for placement in &placements {
+      *uses.entry(placement.audio.clone()).or_default() += 1;
+    }
+
+    assert_eq!(uses.len(), 7);
+    let fewest = uses.values().min().unwrap();
+    let most = uses.values().max().unwrap();
+    assert!(most - fewest <= 1);
+  }
+
+  let without_fill = randomizer::place(1, &dir, &RandomizerOptions::default()).unwrap();
+  assert_eq!(without_fill.len(), 7);
+}
+
+#[test]
+fn fill_leaves_kept_slots_alone() {
+  let dir = common::logs_dir("fill_kept", 3);
+  let options = RandomizerOptions {
+    constraints: serde_json::from_str(r#"{"keep_original": ["tagore_end"]}"#).unwrap(),
+    fill: true,
+    ..Default::default()
+  };
+
+  let placements = randomizer::place(1, &dir, &options).unwrap();
+
+  assert_eq!(placements.len(), DataStore::get_logs().unwrap().len() - 1);
+  assert!(!placements.iter().any(|placement| placement.slot.subtitle == "tagore_end"));
+}
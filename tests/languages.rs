@@ -0,0 +1,242 @@
+use std::{
+  collections::HashMap,
+  fs,
+  path::Path,
+};
+
+use witness_audio_randomizer::core::{
+  randomizer,
+  subtitles,
+  witness_data::{self, SubsInsertionMap, SubtitleFallback},
+};
+
+mod common;
+use common::TempDir;
+
+const KEYS: [&str; 3] = ["schweickart_eva", "tagore_end", "feynman_wine"];
+
+// An install with only subtitles files, in English, German and Japanese
+fn witness_dir(name: &str) -> TempDir {
+  let dir = TempDir::new(&format!("languages_{}", name));
+  fs::create_dir_all(dir.join("data").join("strings")).unwrap();
+
+  for language in ["en", "de", "ja"] {
+    let subtitles: String = KEYS.iter()
+      .map(|key| format!(": {}\r\n\r\n{} text of {}\r\n\r\n\r\n", key, language, key))
+      .collect();
+    fs::write(dir.join("data").join("strings").join(format!("{}.subtitles", language)), subtitles).unwrap();
+  }
+
+  let logs_dir = dir.join("logs");
+  fs::create_dir_all(&logs_dir).unwrap();
+  fs::write(logs_dir.join("translated.sub"), "Translated log").unwrap();
+  fs::write(logs_dir.join("translated.de.sub"), "Übersetztes Log").unwrap();
+  fs::write(logs_dir.join("english.sub"), "English only log").unwrap();
+
+  dir
+}
+
+fn texts(dir: &Path, language: &str) -> HashMap<String, String> {
+  witness_data::load_subtitles_for(dir, language).unwrap().into_iter()
+    .map(|subtitle| (subtitle.key, subtitle.val))
+    .collect()
+}
+
+fn insert(dir: &Path, fallback: SubtitleFallback) {
+  let logs_dir = dir.join("logs");
+  let mut map: SubsInsertionMap = HashMap::new();
+  map.insert("schweickart_eva".to_owned(), Some(logs_dir.join("translated.sub")));
+  map.insert("tagore_end".to_owned(), Some(logs_dir.join("english.sub")));
+
+  witness_data::insert_subtitles(dir, map, fallback).unwrap();
+}
+
+// What older versions did: only `en.subtitles` is changed
+fn insert_english_only(dir: &Path) {
+  let en = dir.join("data").join("strings").join("en.subtitles");
+  let raw = fs::read_to_string(&en).unwrap().replace("en text of schweickart_eva", "Randomized log");
+  fs::write(en, raw).unwrap();
+}
+
+#[test]
+fn every_language_is_backed_up_and_inserted() {
+  let dir = witness_dir("insert");
+
+  assert_eq!(witness_data::subtitle_languages(&dir).unwrap(), ["en", "de", "ja"]);
+  assert!(witness_data::subtitles_need_backing_up(&dir));
+  witness_data::create_subtitles_backup(&dir).unwrap();
+  assert!(!witness_data::subtitles_need_backing_up(&dir));
+
+  insert(&dir, SubtitleFallback::English);
+
+  let en = texts(&dir, "en");
+  assert_eq!(en["schweickart_eva"], "Translated log");
+  assert_eq!(en["tagore_end"], "English only log");
+  assert_eq!(en["feynman_wine"], "en text of feynman_wine");
+
+  let de = texts(&dir, "de");
+  assert_eq!(de["schweickart_eva"], "Übersetztes Log");
+  assert_eq!(de["tagore_end"], "English only log");
+  assert_eq!(de["feynman_wine"], "de text of feynman_wine");
+
+  let ja = texts(&dir, "ja");
+  assert_eq!(ja["schweickart_eva"], "Translated log");
+
+  witness_data::restore_subtitles_backup(&dir).unwrap();
+  assert_eq!(texts(&dir, "de")["schweickart_eva"], "de text of schweickart_eva");
+  assert_eq!(texts(&dir, "ja")["tagore_end"], "ja text of tagore_end");
+}
+
+#[test]
+fn legacy_english_backups_are_kept() {
+  let dir = witness_dir("legacy");
+  let strings_dir = dir.join("data").join("strings");
+
+  // An older version backed up and randomized English only
+  fs::copy(strings_dir.join("en.subtitles"), strings_dir.join("en.subtitles.bak")).unwrap();
+  insert_english_only(&dir);
+  assert!(witness_data::subtitles_backup_is_legacy(&dir));
+  assert!(!witness_data::backup_state(&dir).subtitles_backed_up);
+
+  witness_data::create_subtitles_backup(&dir).unwrap();
+  assert!(!witness_data::subtitles_backup_is_legacy(&dir));
+  assert!(witness_data::backup_state(&dir).subtitles_backed_up);
+
+  insert(&dir, SubtitleFallback::English);
+  witness_data::restore_subtitles_backup(&dir).unwrap();
+  for language in ["en", "de", "ja"] {
+    assert_eq!(texts(&dir, language)["schweickart_eva"], format!("{} text of schweickart_eva", language));
+  }
+}
+
+#[test]
+fn missing_translations_can_be_blank() {
+  let dir = witness_dir("blank");
+  witness_data::create_subtitles_backup(&dir).unwrap();
+
+  insert(&dir, SubtitleFallback::Blank);
+
+  assert_eq!(texts(&dir, "en")["tagore_end"], "English only log");
+  assert_eq!(texts(&dir, "de")["schweickart_eva"], "Übersetztes Log");
+  assert_eq!(texts(&dir, "de")["tagore_end"], "");
+  assert_eq!(texts(&dir, "ja")["schweickart_eva"], "");
+
+  assert_eq!("Blank".parse::<SubtitleFallback>().unwrap(), SubtitleFallback::Blank);
+  assert!("french".parse::<SubtitleFallback>().is_err());
+}
+
+#[test]
+fn invalid_sub_files_change_nothing() {
+  let dir = witness_dir("invalid");
+  witness_data::create_subtitles_backup(&dir).unwrap();
+
+  let logs_dir = dir.join("logs");
+  fs::write(logs_dir.join("bogus.sub"), "Text\n: feynman_wine\nMore").unwrap();
+  fs::write(logs_dir.join("bogus.de.sub"), b"\xffnot text").unwrap();
+
+  let mut map: SubsInsertionMap = HashMap::new();
+  map.insert("schweickart_eva".to_owned(), Some(logs_dir.join("translated.sub")));
+  map.insert("tagore_end".to_owned(), Some(logs_dir.join("bogus.sub")));
+
+  let problems = witness_data::subtitle_problems(&dir, &map, SubtitleFallback::English).unwrap();
+  assert_eq!(problems.len(), 2);
+  assert!(problems.iter().any(|problem| problem.contains("bogus.sub\": line 2 starts with `:`")));
+  assert!(problems.iter().any(|problem| problem.contains("bogus.de.sub\": line 1, column 1: invalid UTF-8")));
+
+  let before = fs::read(dir.join("data").join("strings").join("en.subtitles")).unwrap();
+  let err = witness_data::insert_subtitles(&dir, map, SubtitleFallback::English).unwrap_err().to_string();
+  assert!(err.starts_with("Invalid subtitles files:"));
+  assert!(err.contains("bogus.sub"));
+  assert_eq!(fs::read(dir.join("data").join("strings").join("en.subtitles")).unwrap(), before);
+  assert_eq!(texts(&dir, "en")["schweickart_eva"], "en text of schweickart_eva");
+}
+
+#[test]
+fn sub_files_are_normalized_on_insertion() {
+  let dir = witness_dir("normalized");
+  witness_data::create_subtitles_backup(&dir).unwrap();
+
+  let logs_dir = dir.join("logs");
+  let utf16: Vec<u8> = [0xFF, 0xFE].into_iter()
+    .chain("\n\nFirst line\nSecond line  \n\n\n".encode_utf16().flat_map(u16::to_le_bytes))
+    .collect();
+  fs::write(logs_dir.join("utf16.sub"), utf16).unwrap();
+
+  let mut map: SubsInsertionMap = HashMap::new();
+  map.insert("tagore_end".to_owned(), Some(logs_dir.join("utf16.sub")));
+  witness_data::insert_subtitles(&dir, map, SubtitleFallback::English).unwrap();
+
+  let raw = fs::read_to_string(dir.join("data").join("strings").join("en.subtitles")).unwrap();
+  assert!(raw.contains(": tagore_end\r\n\r\nFirst line\r\nSecond line\r\n\r\n\r\n: feynman_wine"));
+}
+
+#[test]
+fn strict_checks_name_every_offending_file() {
+  let dir = witness_dir("strict");
+  let logs_dir = dir.join("logs");
+  let missing_audio = logs_dir.join("missing.ogg");
+
+  let mut placements = randomizer::feeling_lucky(&missing_audio, &logs_dir.join("english.sub")).unwrap();
+  placements.retain(|placement| KEYS.contains(&placement.slot.subtitle.as_str()));
+  placements[0].subs = None;
+
+  // Without strict mode, only subtitles that can't be inserted stop the run
+  randomizer::check_insertion(&dir, &placements, SubtitleFallback::English, false).unwrap();
+
+  let err = randomizer::check_insertion(&dir, &placements, SubtitleFallback::English, true).unwrap_err().to_string();
+  assert!(err.starts_with("The audio logs can't be inserted:\n"));
+  assert!(err.contains(&format!("Could not read audio file {:?}", missing_audio)));
+  assert!(err.contains(&format!("Audio log {:?} has no subtitles file", missing_audio)));
+  assert!(err.contains("does not exist in the game's data files"));
+
+  placements[1].subs = Some(logs_dir.join("missing.sub"));
+  let err = randomizer::check_insertion(&dir, &placements, SubtitleFallback::English, false).unwrap_err().to_string();
+  assert!(err.contains("missing.sub"));
+}
+
+#[test]
+fn missing_subtitle_keys_are_reported() {
+  let dir = witness_dir("missing_keys");
+  let strings_dir = dir.join("data").join("strings");
+  fs::write(strings_dir.join("ja.subtitles"), ": schweickart_eva\r\n\r\nja text\r\n\r\n\r\n").unwrap();
+  let before = fs::read(strings_dir.join("en.subtitles")).unwrap();
+
+  let mut map: SubsInsertionMap = HashMap::new();
+  map.insert("tagore_end".to_owned(), Some(dir.join("logs").join("english.sub")));
+  map.insert("not_a_key".to_owned(), Some(dir.join("logs").join("english.sub")));
+
+  let problems = witness_data::subtitle_problems(&dir, &map, SubtitleFallback::English).unwrap();
+  assert_eq!(problems.len(), 4);
+  assert!(problems.iter().any(|problem| problem.contains("`tagore_end` is not in") && problem.contains("ja.subtitles")));
+  assert!(problems.iter().any(|problem| problem.contains("`not_a_key` is not in") && problem.contains("en.subtitles")));
+
+  assert!(witness_data::insert_subtitles(&dir, map, SubtitleFallback::English).is_err());
+  assert_eq!(fs::read(strings_dir.join("en.subtitles")).unwrap(), before);
+}
+
+#[test]
+fn transcripts_are_found_and_converted() {
+  let dir = witness_dir("transcripts");
+  witness_data::create_subtitles_backup(&dir).unwrap();
+
+  let logs_dir = dir.join("logs");
+  fs::write(logs_dir.join("talk.srt"), "1\r\n00:00:01,000 --> 00:00:02,000\r\nFirst cue\r\n\r\n2\r\n00:00:02,000 --> 00:00:03,000\r\nSecond cue\r\n").unwrap();
+  fs::write(logs_dir.join("talk.de.vtt"), "WEBVTT\n\n00:01.000 --> 00:02.000\nErster\n").unwrap();
+  fs::write(logs_dir.join("notes.txt"), "Plain text\n").unwrap();
+
+  let talk = subtitles::find_subs_file(&logs_dir.join("talk.ogg"), None).unwrap();
+  assert_eq!(talk, logs_dir.join("talk.srt"));
+  assert_eq!(subtitles::find_subs_file(&talk, Some("de")), Some(logs_dir.join("talk.de.vtt")));
+  assert_eq!(subtitles::find_subs_file(&logs_dir.join("translated.ogg"), None), Some(logs_dir.join("translated.sub")));
+  assert_eq!(subtitles::find_subs_file(&logs_dir.join("missing.ogg"), None), None);
+
+  let mut map: SubsInsertionMap = HashMap::new();
+  map.insert("schweickart_eva".to_owned(), Some(talk));
+  map.insert("tagore_end".to_owned(), subtitles::find_subs_file(&logs_dir.join("notes.ogg"), None));
+  witness_data::insert_subtitles(&dir, map, SubtitleFallback::English).unwrap();
+
+  let raw = fs::read_to_string(dir.join("data").join("strings").join("en.subtitles")).unwrap();
+  assert!(raw.contains(": schweickart_eva\r\n\r\nFirst cue\r\n\r\nSecond cue\r\n\r\n\r\n"));
+  assert_eq!(texts(&dir, "de")["schweickart_eva"], "Erster");
+  assert_eq!(texts(&dir, "en")["tagore_end"], "Plain text");
+}
@@ -0,0 +1,161 @@
+use std::{
+  fs,
+  io::{Cursor, Write},
+};
+use zip::write::FileOptions;
+
+use witness_audio_randomizer::core::{
+  pack::{self, LogInfo},
+  randomizer::{self, RandomizerOptions},
+  sources::{self, LogFilter},
+  spoiler::SpoilerLog,
+  weights::Weight,
+};
+
+mod common;
+use common::TempDir;
+
+const MANIFEST: &str = r#"{
+  "name": "Lectures",
+  "author": "Pack Author",
+  "logs": [
+    {"file": "wine.ogg", "title": "A glass of wine", "speaker": "Richard Feynman", "tags": ["physics"], "weight": "always"},
+    {"file": "logs/atoms.ogg", "subtitles": "text/atoms.txt", "author": "Someone Else"}
+  ]
+}"#;
+
+fn zip_files(files: &[(&str, &str)]) -> Vec<u8> {
+  let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+
+  for (name, contents) in files {
+    zip.start_file(*name, FileOptions::default()).unwrap();
+    zip.write_all(contents.as_bytes()).unwrap();
+  }
+
+  zip.finish().unwrap().into_inner()
+}
+
+fn logs_dir(name: &str) -> TempDir {
+  let dir = TempDir::new(&format!("packs_{}", name));
+
+  fs::write(dir.join("loose.ogg"), "OggS loose").unwrap();
+  fs::write(dir.join("lectures.zip"), zip_files(&[
+    ("pack.json", MANIFEST),
+    ("wine.ogg", "OggS wine"),
+    ("wine.sub", "Wine subtitles"),
+    ("logs/atoms.ogg", "OggS atoms"),
+    ("text/atoms.txt", "Atoms subtitles"),
+    ("unlisted.ogg", "OggS unlisted"),
+  ])).unwrap();
+
+  dir
+}
+
+#[test]
+fn packs_are_read_as_sources() {
+  let dir = logs_dir("sources");
+
+  let logs = sources::find_logs(&dir, &LogFilter::default()).unwrap();
+  let paths: Vec<String> = logs.iter().map(|log| log.relative_path.to_string_lossy().replace('\\', "/")).collect();
+  assert_eq!(paths, vec!["lectures/logs/atoms.ogg", "lectures/wine.ogg", "loose.ogg"]);
+
+  let wine = &logs[1];
+  assert_eq!(wine.pack.as_deref(), Some("lectures"));
+  assert_eq!(wine.weight, Some(Weight::Always));
+  assert_eq!(fs::read_to_string(&wine.path).unwrap(), "OggS wine");
+  assert_eq!(fs::read_to_string(wine.subs.as_ref().unwrap()).unwrap(), "Wine subtitles");
+  assert_eq!(wine.info, Some(LogInfo {
+    title: Some("A glass of wine".to_owned()),
+    speaker: Some("Richard Feynman".to_owned()),
+    author: Some("Pack Author".to_owned()),
+    tags: vec!["physics".to_owned()],
+  }));
+
+  let atoms = &logs[0];
+  assert_eq!(fs::read_to_string(atoms.subs.as_ref().unwrap()).unwrap(), "Atoms subtitles");
+  assert_eq!(atoms.info.as_ref().unwrap().author.as_deref(), Some("Someone Else"));
+
+  let disabled = LogFilter { disabled_packs: vec!["lectures".to_owned()], ..Default::default() };
+  assert_eq!(sources::find_logs(&dir, &disabled).unwrap().len(), 1);
+
+  // A pack file can also be used on its own
+  assert_eq!(sources::find_logs(&dir.join("lectures.zip"), &LogFilter::default()).unwrap().len(), 2);
+}
+
+#[test]
+fn pack_info_flows_into_spoilers() {
+  let dir = logs_dir("spoiler");
+
+  let placements = randomizer::place(3, &dir, &RandomizerOptions::default()).unwrap();
+  let spoiler = SpoilerLog::new(Some("3"), &placements);
+
+  let wine = spoiler.placements.iter()
+    .find(|entry| entry.source.ends_with("wine.ogg"))
+    .unwrap();
+  assert_eq!(wine.pack.as_deref(), Some("lectures"));
+  assert_eq!(wine.info.as_ref().unwrap().title.as_deref(), Some("A glass of wine"));
+
+  let text = spoiler.to_text();
+  assert!(text.contains("  title:  A glass of wine\n"));
+  assert!(text.contains("  by:     Richard Feynman\n"));
+}
+
+#[test]
+fn invalid_packs_are_rejected() {
+  assert!(pack::parse_manifest(r#"{"logs": [{"title": "no file"}]}"#).is_err());
+
+  let dir = logs_dir("invalid");
+  fs::write(dir.join("broken.zip"), zip_files(&[
+    ("pack.json", r#"{"logs": [{"file": "missing.ogg"}]}"#),
+  ])).unwrap();
+
+  let err = sources::find_logs(&dir, &LogFilter::default()).unwrap_err().to_string();
+  assert!(err.contains("`missing.ogg` is listed in pack.json but is not in the pack"));
+}
+
+#[test]
+fn zips_without_manifest_are_skipped() {
+  let dir = logs_dir("no_manifest");
+  fs::write(dir.join("backup.zip"), zip_files(&[("old.ogg", "OggS old")])).unwrap();
+
+  let logs = sources::find_logs(&dir, &LogFilter::default()).unwrap();
+  assert_eq!(logs.len(), 3);
+  assert!(!logs.iter().any(|log| log.relative_path.starts_with("backup")));
+}
+
+#[test]
+fn concurrent_runs_share_the_pack_cache() {
+  let dir = logs_dir("concurrent");
+  let pack_path = dir.join("lectures.zip");
+
+  let loads: Vec<_> = (0..8)
+    .map(|_| {
+      let pack_path = pack_path.clone();
+      std::thread::spawn(move || pack::load_pack(&pack_path))
+    })
+    .collect();
+
+  for load in loads {
+    let logs = load.join().unwrap().unwrap();
+    assert_eq!(fs::read_to_string(&logs[0].audio).unwrap(), "OggS wine");
+  }
+}
+
+#[test]
+fn stale_pack_versions_are_evicted() {
+  let dir = logs_dir("evicted");
+  let pack_path = dir.join("lectures.zip");
+
+  let old = pack::load_pack(&pack_path).unwrap();
+  let old_version = old[0].audio.parent().unwrap().to_owned();
+
+  fs::write(&pack_path, zip_files(&[
+    ("pack.json", r#"{"logs": [{"file": "wine.ogg"}]}"#),
+    ("wine.ogg", "OggS new wine"),
+  ])).unwrap();
+
+  let new = pack::load_pack(&pack_path).unwrap();
+  assert_eq!(fs::read_to_string(&new[0].audio).unwrap(), "OggS new wine");
+  assert_ne!(new[0].audio.parent().unwrap(), old_version);
+  assert!(!old_version.exists());
+}
@@ -0,0 +1,59 @@
+use std::fs;
+
+use witness_audio_randomizer::core::{
+  plando::Plando,
+  randomizer,
+};
+
+mod common;
+use common::TempDir;
+
+fn plando_dir(name: &str) -> TempDir {
+  let dir = TempDir::new(&format!("plando_{}", name));
+  fs::create_dir_all(dir.join("logs")).unwrap();
+  fs::create_dir_all(dir.join("subs")).unwrap();
+
+  fs::write(dir.join("logs").join("bouncer.ogg"), b"OggS").unwrap();
+  fs::write(dir.join("logs").join("bouncer.sub"), "The bouncer").unwrap();
+  fs::write(dir.join("logs").join("outro.ogg"), b"OggS").unwrap();
+  fs::write(dir.join("subs").join("outro.sub"), "The outro").unwrap();
+
+  dir
+}
+
+#[test]
+fn plando_places_logs_exactly() {
+  let dir = plando_dir("exact");
+  fs::write(dir.join("plando.json"), r#"{
+    "schweickart_eva": "logs/bouncer.ogg",
+    "tagore_end": { "audio": "logs/outro.ogg", "subtitles": "subs/outro.sub" },
+    "feynman_wine": null
+  }"#).unwrap();
+
+  let placements = Plando::load(&dir.join("plando.json")).unwrap().placements().unwrap();
+  assert_eq!(placements.len(), 2);
+
+  let (logs, subs) = randomizer::insertion_maps(&placements);
+  assert_eq!(logs.values().map(Vec::len).sum::<usize>(), 2);
+  assert_eq!(subs["schweickart_eva"], Some(dir.join("logs").join("bouncer.sub")));
+  assert_eq!(subs["tagore_end"], Some(dir.join("subs").join("outro.sub")));
+  assert!(!subs.contains_key("feynman_wine"));
+}
+
+#[test]
+fn invalid_plando_files_are_reported() {
+  let dir = plando_dir("invalid");
+  let plando = Plando::parse(r#"{
+    "not_a_slot": "logs/bouncer.ogg",
+    "tagore_end": "logs/missing.ogg",
+    "schweickart_eva": { "audio": "logs/outro.ogg", "subtitles": "subs/missing.sub" }
+  }"#, &dir).unwrap();
+
+  let err = plando.placements().unwrap_err().to_string();
+
+  assert!(err.contains("Unknown slot `not_a_slot`"));
+  assert!(err.contains("missing.ogg"));
+  assert!(err.contains("missing.sub"));
+
+  assert!(Plando::parse(r#"{ "tagore_end": 3 }"#, &dir).is_err());
+}
@@ -0,0 +1,105 @@
+use std::{
+  collections::BTreeMap,
+  path::Path,
+};
+
+use witness_audio_randomizer::core::{
+  randomizer::{self, RandomizerOptions},
+  seed,
+};
+
+mod common;
+
+fn placements(seed: u64, dir: &Path) -> BTreeMap<String, String> {
+  let (logs, _subs) = randomizer::randomize(seed, dir, &RandomizerOptions::default()).unwrap();
+
+  logs.into_values()
+    .flatten()
+    .map(|insertion| (
+      insertion.dest_file.to_string_lossy().to_string(),
+      insertion.source_file.file_name().unwrap().to_string_lossy().to_string(),
+    ))
+    .collect()
+}
+
+fn as_map(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+  pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+#[test]
+fn seed_strings() {
+  assert_eq!(seed::seed_from_str("DEADBEEF"), 0xDEADBEEF);
+  assert_eq!(seed::seed_from_str("deadbeef"), 0xDEADBEEF);
+  assert_eq!(seed::seed_from_str("0x1f"), 0x1F);
+  assert_eq!(seed::seed_from_str("  1F\n"), 0x1F);
+  assert_eq!(seed::seed_from_str("FFFFFFFFFFFFFFFF"), u64::MAX);
+
+  // FNV-1a reference values
+  assert_eq!(seed::seed_from_str(""), 0xcbf29ce484222325);
+  assert_eq!(seed::seed_from_str("foobar"), 0x85944171f73967e8);
+  assert_eq!(seed::seed_from_str("hello world"), 0x779a65e7023cd2e7);
+
+  // Too long to be a hex seed
+  assert_eq!(seed::seed_from_str("10000000000000000"), seed::fnv1a_64(b"10000000000000000"));
+}
+
+#[test]
+fn daily_seeds() {
+  assert_eq!(seed::UtcDate::from_unix_days(0).to_string(), "1970-01-01");
+  assert_eq!(seed::UtcDate::from_unix_days(11016).to_string(), "2000-02-29");
+  assert_eq!(seed::UtcDate::from_unix_days(20000).to_string(), "2024-10-04");
+  assert_eq!(seed::UtcDate::from_unix_days(-1).to_string(), "1969-12-31");
+
+  let date = seed::UtcDate { year: 2024, month: 10, day: 4 };
+  assert_eq!(seed::daily_seed_string(date, None), "daily-2024-10-04");
+  assert_eq!(seed::daily_seed_string(date, Some(" ")), "daily-2024-10-04");
+  assert_eq!(seed::daily_seed_string(date, Some("our group")), "daily-2024-10-04-our group");
+  assert_eq!(seed::seed_from_str("daily-2024-10-04"), seed::fnv1a_64(b"daily-2024-10-04"));
+}
+
+#[test]
+fn rng_stream() {
+  let mut rng = seed::rng_from_seed(0);
+  let indices: Vec<usize> = (0..8).map(|_| seed::gen_index(&mut rng, 100)).collect();
+  assert_eq!(indices, GOLDEN_INDICES);
+
+  let mut items: Vec<u32> = (0..10).collect();
+  seed::shuffle(&mut items, &mut seed::rng_from_seed(seed::seed_from_str("witness")));
+  assert_eq!(items, GOLDEN_SHUFFLE);
+}
+
+// Golden seed -> placement vectors. If any of these change, shared seeds no longer reproduce across
+// builds, and `seed::SEED_SCHEME_VERSION` has to be bumped.
+#[test]
+fn golden_placements() {
+  let dir = common::logs_dir("seeds_golden", 5);
+
+  assert_eq!(placements(seed::seed_from_str("C0FFEE"), &dir), as_map(GOLDEN_C0FFEE));
+  assert_eq!(placements(seed::seed_from_str("the bouncer"), &dir), as_map(GOLDEN_BOUNCER));
+}
+
+#[test]
+fn placements_are_deterministic() {
+  let dir = common::logs_dir("seeds_deterministic", 12);
+
+  let seed = seed::seed_from_str("1234ABCD");
+  assert_eq!(placements(seed, &dir), placements(seed, &dir));
+  assert_eq!(placements(seed, &dir).len(), 12);
+}
+
+const GOLDEN_INDICES: [usize; 8] = [81, 33, 24, 38, 61, 78, 30, 81];
+const GOLDEN_SHUFFLE: [u32; 10] = [4, 9, 6, 7, 2, 3, 0, 1, 5, 8];
+const GOLDEN_C0FFEE: &[(&str, &str)] = &[
+  ("eddington_generation_of_waves.sound", "log_00.ogg"),
+  ("einstein_searchers.sound", "log_01.ogg"),
+  ("niffari_sea.sound", "log_03.ogg"),
+  ("sandwich.sound", "log_04.ogg"),
+  ("schweickart_eva.sound", "log_02.ogg"),
+];
+const GOLDEN_BOUNCER: &[(&str, &str)] = &[
+  ("abbad_wine.sound", "log_00.ogg"),
+  ("cusa_name.sound", "log_01.ogg"),
+  ("einstein_cosmic_religious_feeling.sound", "log_02.ogg"),
+  ("einstein_searchers.sound", "log_03.ogg"),
+  ("wordsworth_peak.sound", "log_04.ogg"),
+];
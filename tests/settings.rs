@@ -0,0 +1,81 @@
+use std::{
+  fs,
+  path::Path,
+};
+
+use witness_audio_randomizer::core::{
+  randomizer,
+  seed,
+  settings::{self, Settings},
+  sources::LogFilter,
+};
+
+mod common;
+use common::TempDir;
+
+fn logs_dir(name: &str) -> TempDir {
+  let dir = TempDir::new(&format!("settings_{}", name));
+
+  for i in 0..20 {
+    fs::write(dir.join(format!("log_{:02}.ogg", i)), format!("OggS {}", i)).unwrap();
+  }
+  fs::write(dir.join("log_00.sub"), "Subtitles").unwrap();
+
+  dir
+}
+
+#[test]
+fn settings_strings_round_trip() {
+  let mut settings = Settings {
+    fill: true,
+    mix: Some("30%".to_owned()),
+    exclude: vec!["*_intro.ogg".to_owned()],
+    max_per_area: Some(3),
+    constraints: serde_json::from_str(r#"{ "keep_original": ["tagore_end"] }"#).unwrap(),
+    fingerprint: Some(0x0123456789abcdef),
+    ..Settings::new("daily-2024-10-04")
+  };
+
+  let encoded = settings.to_string();
+  assert!(encoded.starts_with("WR1."));
+  assert_eq!(encoded.parse::<Settings>().unwrap(), settings);
+  assert_eq!(format!("  {}\n", encoded).parse::<Settings>().unwrap(), settings);
+
+  settings.fill = false;
+  assert_ne!(settings.to_string(), encoded);
+
+  // A changed character anywhere is caught by the checksum
+  let mut tampered: Vec<char> = encoded.chars().collect();
+  tampered[10] = if tampered[10] == 'A' { 'B' } else { 'A' };
+  let err = tampered.iter().collect::<String>().parse::<Settings>().unwrap_err().to_string();
+  assert!(err.contains("mistyped or incomplete"));
+
+  assert!(encoded[..encoded.len() - 4].parse::<Settings>().is_err());
+  assert!(encoded.replacen("WR1.", "WR9.", 1).parse::<Settings>().unwrap_err().to_string().contains("version 9"));
+  assert!("DEADBEEF".parse::<Settings>().is_err());
+}
+
+#[test]
+fn fingerprints_and_check_words_follow_contents() {
+  let dir = logs_dir("fingerprint");
+  let copy = logs_dir("fingerprint_copy");
+  let filter = LogFilter::default();
+
+  let fingerprint = randomizer::fingerprint(&dir, &filter).unwrap();
+  assert_eq!(randomizer::fingerprint(&copy, &filter).unwrap(), fingerprint);
+
+  let settings = Settings::new("shared");
+  let options = settings.options(None).unwrap();
+  let check = |dir: &Path| {
+    let placements = randomizer::place(seed::seed_from_str(&settings.seed), dir, &options).unwrap();
+    settings::check_words(&placements).unwrap()
+  };
+
+  let words = check(&dir);
+  assert_eq!(words.split('-').count(), 4);
+  assert_eq!(check(&copy), words);
+
+  fs::write(copy.join("log_00.sub"), "Other subtitles").unwrap();
+  assert_ne!(randomizer::fingerprint(&copy, &filter).unwrap(), fingerprint);
+  assert_ne!(check(&copy), words);
+}
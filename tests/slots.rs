@@ -0,0 +1,84 @@
+use std::{
+  collections::HashSet,
+  fs,
+};
+
+use witness_audio_randomizer::core::witness_data::{self, DataStore, Subtitle};
+
+mod common;
+use common::TempDir;
+
+fn config_dir(name: &str) -> TempDir {
+  let dir = TempDir::new(&format!("slots_{}", name));
+  fs::create_dir_all(dir.join("logs.d")).unwrap();
+
+  dir
+}
+
+#[test]
+fn slot_catalog_is_consistent() {
+  let slots = DataStore::embedded_logs().unwrap();
+
+  let subtitles: HashSet<&str> = slots.iter().map(|slot| slot.subtitle.as_str()).collect();
+  assert_eq!(subtitles.len(), slots.len());
+  assert!(slots.iter().all(|slot| slot.area.is_none()));
+}
+
+#[test]
+fn slot_overlays_are_merged() {
+  let dir = config_dir("merged");
+  fs::write(dir.join("logs.json"), r#"[
+    { "subtitle": "schweickart_eva", "area": "Mountain" },
+    { "subtitle": "tagore_end", "area": "Ending" },
+    { "subtitle": "new_slot", "package": "globals.pkg", "filename": "new_slot.sound" }
+  ]"#).unwrap();
+  fs::write(dir.join("logs.d").join("10-corrections.json"), r#"[
+    { "subtitle": "tagore_end", "area": null },
+    { "subtitle": "new_slot", "area": "Quarry" }
+  ]"#).unwrap();
+
+  let embedded = DataStore::embedded_logs().unwrap();
+  let slots = DataStore::load_logs(&dir).unwrap();
+  assert_eq!(slots.len(), embedded.len() + 1);
+
+  let mountaintop = slots.iter().find(|slot| slot.subtitle == "schweickart_eva").unwrap();
+  assert_eq!(mountaintop.area.as_deref(), Some("Mountain"));
+  assert!(slots.iter().find(|slot| slot.subtitle == "tagore_end").unwrap().area.is_none());
+
+  let new_slot = slots.iter().find(|slot| slot.subtitle == "new_slot").unwrap();
+  assert_eq!(new_slot.area.as_deref(), Some("Quarry"));
+}
+
+#[test]
+fn invalid_slot_overlays_are_reported() {
+  let dir = config_dir("invalid");
+  fs::write(dir.join("logs.json"), r#"[
+    { "subtitle": "schweickart_eva", "area": 5 },
+    { "subtitle": "new_slot", "area": "Quarry" },
+    { "subtitle": "tagore_end", "aera": "Quarry" },
+    { "area": "Quarry" }
+  ]"#).unwrap();
+  fs::write(dir.join("logs.d").join("broken.json"), "{").unwrap();
+
+  let err = DataStore::load_logs(&dir).unwrap_err().to_string();
+
+  assert!(err.contains("slot `schweickart_eva`"));
+  assert!(err.contains("slot `new_slot`: missing field `filename`"));
+  assert!(err.contains("slot `tagore_end`: unknown field `aera`"));
+  assert!(err.contains("entry 4 has no `subtitle`"));
+  assert!(err.contains("broken.json"));
+}
+
+#[test]
+fn dumping_reports_slots_without_subtitles() {
+  let dir = config_dir("dump");
+  let slots = DataStore::embedded_logs().unwrap();
+  let subs: Vec<Subtitle> = slots.iter()
+    .filter(|slot| slot.subtitle != "tagore_end")
+    .map(|slot| Subtitle { key: slot.subtitle.clone(), val: String::new() })
+    .collect();
+
+  let err = witness_data::dump_logs(&dir, &dir, &slots, &subs).unwrap_err().to_string();
+  assert!(err.contains("Slot `tagore_end` has no subtitle"));
+  assert!(!dir.join("feynman_atoms_with_curiosity.ogg").exists());
+}
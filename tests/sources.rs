@@ -0,0 +1,74 @@
+use std::{
+  fs,
+  path::Path,
+};
+
+use witness_audio_randomizer::core::sources::{self, LogFilter};
+
+mod common;
+use common::TempDir;
+
+fn logs_dir(name: &str) -> TempDir {
+  let dir = TempDir::new(&format!("sources_{}", name));
+
+  for path in [
+    "top.ogg",
+    "LOUD.OGG",
+    "notes.txt",
+    "feynman/wine.ogg",
+    "feynman/extra/atoms.oga",
+    "feynman/atoms.sub",
+    "tagore/voyage_intro.Ogg",
+    "tagore/boast.ogg",
+  ] {
+    let path = dir.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, b"OggS").unwrap();
+  }
+
+  dir
+}
+
+fn found(dir: &Path, filter: &LogFilter) -> Vec<String> {
+  sources::find_logs(dir, filter).unwrap().into_iter()
+    .map(|log| log.relative_path.to_string_lossy().replace('\\', "/"))
+    .collect()
+}
+
+#[test]
+fn finds_logs_recursively() {
+  let dir = logs_dir("recursive");
+
+  assert_eq!(found(&dir, &LogFilter::default()), vec![
+    "LOUD.OGG",
+    "feynman/extra/atoms.oga",
+    "feynman/wine.ogg",
+    "tagore/boast.ogg",
+    "tagore/voyage_intro.Ogg",
+    "top.ogg",
+  ]);
+
+  let packs: Vec<(String, usize)> = sources::list_packs(&dir).unwrap().into_iter().collect();
+  assert_eq!(packs, vec![("feynman".to_owned(), 2), ("tagore".to_owned(), 2)]);
+}
+
+#[test]
+fn filters_logs() {
+  let dir = logs_dir("filters");
+
+  let packs_only = LogFilter { enabled_packs: Some(vec!["Tagore".to_owned()]), ..Default::default() };
+  assert_eq!(found(&dir, &packs_only), vec!["LOUD.OGG", "tagore/boast.ogg", "tagore/voyage_intro.Ogg", "top.ogg"]);
+
+  let disabled = LogFilter { disabled_packs: vec!["tagore".to_owned()], ..Default::default() };
+  assert_eq!(found(&dir, &disabled), vec!["LOUD.OGG", "feynman/extra/atoms.oga", "feynman/wine.ogg", "top.ogg"]);
+
+  let globs = LogFilter {
+    include: vec!["feynman/**/*".to_owned(), "*_INTRO.ogg".to_owned()],
+    exclude: vec!["**/atoms.*".to_owned()],
+    ..Default::default()
+  };
+  assert_eq!(found(&dir, &globs), vec!["feynman/wine.ogg", "tagore/voyage_intro.Ogg"]);
+
+  let invalid = LogFilter { include: vec!["[".to_owned()], ..Default::default() };
+  assert!(sources::find_logs(&dir, &invalid).is_err());
+}
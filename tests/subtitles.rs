@@ -0,0 +1,170 @@
+use proptest::prelude::*;
+
+use witness_audio_randomizer::core::subtitles::{self, SubtitlesFile};
+
+// Text that survives being trimmed and cannot be mistaken for a key line
+fn text() -> impl Strategy<Value = String> {
+  prop::collection::vec("[A-Za-zÀ-ÿ0-9,.!?']{1,10}", 1..8).prop_map(|words| words.join(" "))
+}
+
+fn entries() -> impl Strategy<Value = Vec<(String, String)>> {
+  prop::collection::vec(("[a-z][a-z0-9_]{0,15}", text()), 1..12)
+}
+
+// A file laid out the way the game writes them
+fn file(entries: &[(String, String)], line_ending: &str, bom: bool) -> String {
+  let mut raw = String::from(if bom { "\u{feff}" } else { "" });
+
+  for (key, text) in entries {
+    raw.push_str(&format!(": {}{le}{le}{}{le}{le}{le}", key, text, le = line_ending));
+  }
+
+  raw
+}
+
+proptest! {
+  #[test]
+  fn any_parsed_file_round_trips(raw in prop::collection::vec(
+    prop_oneof![
+      Just(": key\r\n".to_owned()),
+      Just(":other\n".to_owned()),
+      Just(":".to_owned()),
+      Just("\r\n".to_owned()),
+      Just("\n".to_owned()),
+      Just("\r".to_owned()),
+      Just(" ".to_owned()),
+      Just("\u{feff}".to_owned()),
+      "[a-zé: ]{1,8}",
+    ],
+    0..40,
+  )) {
+    let raw = raw.concat();
+    if let Ok(subtitles) = SubtitlesFile::parse(raw.as_bytes()) {
+      prop_assert_eq!(subtitles.to_bytes(), raw.into_bytes());
+    }
+  }
+
+  #[test]
+  fn any_bytes_round_trip_or_fail(raw in prop::collection::vec(any::<u8>(), 0..200)) {
+    if let Ok(subtitles) = SubtitlesFile::parse(&raw) {
+      prop_assert_eq!(subtitles.to_bytes(), raw);
+    }
+  }
+
+  #[test]
+  fn game_files_parse_and_round_trip(
+    entries in entries(),
+    crlf in any::<bool>(),
+    bom in any::<bool>(),
+  ) {
+    let raw = file(&entries, if crlf { "\r\n" } else { "\n" }, bom);
+    let subtitles = SubtitlesFile::parse(raw.as_bytes()).unwrap();
+
+    prop_assert_eq!(subtitles.entries().len(), entries.len());
+    for (entry, (key, text)) in subtitles.entries().iter().zip(&entries) {
+      prop_assert_eq!(entry.key(), key);
+      prop_assert_eq!(entry.text(), text);
+    }
+    prop_assert_eq!(subtitles.to_bytes(), raw.into_bytes());
+  }
+
+  #[test]
+  fn setting_text_only_changes_that_entry(
+    entries in entries(),
+    crlf in any::<bool>(),
+    index in any::<prop::sample::Index>(),
+    new_text in text(),
+  ) {
+    let line_ending = if crlf { "\r\n" } else { "\n" };
+    let mut subtitles = SubtitlesFile::parse(file(&entries, line_ending, false).as_bytes()).unwrap();
+
+    let key = &entries[index.index(entries.len())].0;
+    prop_assert!(subtitles.set_text(key, &new_text));
+
+    // The result is what the game would have written with the new text
+    let expected: Vec<(String, String)> = entries.iter()
+      .map(|(entry_key, text)| (entry_key.clone(), if entry_key == key { new_text.clone() } else { text.clone() }))
+      .collect();
+    prop_assert_eq!(subtitles.to_bytes(), file(&expected, line_ending, false).into_bytes());
+  }
+}
+
+#[test]
+fn loose_layouts_are_accepted() {
+  let raw = "\u{feff}: first\nNo blank line\n:second  \r\n\r\n  Spaced  \r\n: empty";
+  let mut subtitles = SubtitlesFile::parse(raw.as_bytes()).unwrap();
+
+  assert_eq!(subtitles.get("first"), Some("No blank line"));
+  assert_eq!(subtitles.get("second"), Some("Spaced"));
+  assert_eq!(subtitles.get("empty"), Some(""));
+  assert_eq!(subtitles.to_bytes(), raw.as_bytes());
+
+  subtitles.set_text("first", "Replaced");
+  subtitles.set_text("empty", "Filled");
+  assert!(!subtitles.set_text("missing", "Nowhere"));
+
+  let reparsed = SubtitlesFile::parse(&subtitles.to_bytes()).unwrap();
+  assert_eq!(reparsed.get("first"), Some("Replaced"));
+  assert_eq!(reparsed.get("second"), Some("Spaced"));
+  assert_eq!(reparsed.get("empty"), Some("Filled"));
+  assert!(String::from_utf8(subtitles.to_bytes()).unwrap().starts_with("\u{feff}: first\nReplaced\n:second"));
+}
+
+#[test]
+fn errors_have_positions() {
+  let err = SubtitlesFile::parse(b": fine\r\n\r\nText\r\n:  \r\n").unwrap_err();
+  assert_eq!((err.line, err.column), (4, 2));
+
+  let err = SubtitlesFile::parse(b": fine\r\n\r\nT\xc3\xa9xt \xff\r\n").unwrap_err();
+  assert_eq!((err.line, err.column), (3, 6));
+  assert_eq!(err.to_string(), "line 3, column 6: invalid UTF-8");
+}
+
+#[test]
+fn sub_files_are_decoded_and_cleaned() {
+  let utf16le: Vec<u8> = [0xFF, 0xFE].into_iter()
+    .chain("Grüße\r\nzwei".encode_utf16().flat_map(u16::to_le_bytes))
+    .collect();
+  let utf16be: Vec<u8> = [0xFE, 0xFF].into_iter()
+    .chain("Grüße\nzwei".encode_utf16().flat_map(u16::to_be_bytes))
+    .collect();
+  assert_eq!(subtitles::decode_text(&utf16le).unwrap(), "Grüße\r\nzwei");
+  assert_eq!(subtitles::decode_text(&utf16be).unwrap(), "Grüße\nzwei");
+  assert_eq!(subtitles::decode_text("\u{feff}Hi".as_bytes()).unwrap(), "Hi");
+  assert!(subtitles::decode_text(&utf16le[..5]).is_err());
+
+  let err = subtitles::decode_text(b"fine\n\xff").unwrap_err();
+  assert_eq!(err.to_string(), "line 2, column 1: invalid UTF-8 (UTF-16 files need a byte order mark)");
+
+  assert_eq!(subtitles::clean_text("\n\n  One  \r\nTwo\rThree\n\n\n").unwrap(), "One\nTwo\nThree");
+  assert_eq!(subtitles::clean_text("Not: a key").unwrap(), "Not: a key");
+  assert_eq!(subtitles::clean_text("  :first line").unwrap_err().to_string().as_str(),
+    "line 1 starts with `:`, which would start a new subtitle");
+  assert_eq!(subtitles::clean_text("\r\n\r\nText\r\n: bogus_key\r\n").unwrap_err().to_string().as_str(),
+    "line 4 starts with `:`, which would start a new subtitle");
+}
+
+#[test]
+fn inserted_text_uses_the_entry_line_endings() {
+  let mut subtitles = SubtitlesFile::parse(b": key\r\n\r\nOld\r\n\r\n\r\n").unwrap();
+  subtitles.set_text("key", "New\nlines\n\n");
+  assert_eq!(subtitles.to_bytes(), b": key\r\n\r\nNew\r\nlines\r\n\r\n\r\n");
+}
+
+#[test]
+fn srt_transcripts_become_paragraphs() {
+  let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:04,000\r\n<i>Up there</i> you go around\r\nevery hour and a half.\r\n\r\n\
+    2\r\n00:00:04,500 --> 00:00:07,000\r\n{\\an8}Time after time {sic}\r\n\r\n\r\n3\r\n00:00:08,000 --> 00:00:09,000\r\n<b></b>\r\n";
+
+  assert_eq!(subtitles::srt_to_text(srt), "Up there you go around\nevery hour and a half.\n\nTime after time {sic}");
+}
+
+#[test]
+fn vtt_transcripts_become_paragraphs() {
+  let vtt = "WEBVTT - exported\n\nNOTE edited by hand\nover two lines\n\nSTYLE\n::cue { color: lime }\n\n\
+    intro\n00:01.000 --> 00:04.000 align:start\n<v Schweickart>Up there &amp; around</v>\n\n\
+    00:04.500 --> 00:07.000\nTime after <00:05.000>time\n";
+
+  assert_eq!(subtitles::vtt_to_text(vtt).unwrap(), "Up there & around\n\nTime after time");
+  assert!(subtitles::vtt_to_text("1\n00:01.000 --> 00:04.000\nText\n").is_err());
+}
@@ -0,0 +1,121 @@
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  fs,
+  io::{Cursor, Write},
+  path::{Path, PathBuf},
+};
+use zip::write::FileOptions;
+
+use witness_audio_randomizer::core::{
+  randomizer::{self, CustomShare, RandomizerOptions},
+  witness_data::{self, AudioLog, DataStore},
+};
+
+mod common;
+use common::TempDir;
+
+fn sound(contents: &str) -> Vec<u8> {
+  let mut bytes = vec![0x0B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00];
+  bytes.extend((contents.len() as u32).to_le_bytes());
+  bytes.extend(contents.as_bytes());
+  bytes
+}
+
+fn zip_files(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+  let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+
+  for (name, contents) in files {
+    zip.start_file(name.as_str(), FileOptions::default()).unwrap();
+    zip.write_all(contents).unwrap();
+  }
+
+  zip.finish().unwrap().into_inner()
+}
+
+// A backed up install containing only the audio logs and their subtitles
+fn witness_dir(name: &str, logs: &[AudioLog]) -> TempDir {
+  let dir = TempDir::new(&format!("vanilla_{}", name));
+  fs::create_dir_all(dir.join("data").join("strings")).unwrap();
+
+  let mut packages: BTreeMap<Option<PathBuf>, Vec<(String, Vec<u8>)>> = BTreeMap::new();
+  for log in logs {
+    let name = log.filename.to_string_lossy().to_string();
+    packages.entry(log.package.clone()).or_default().push((name, sound(&log.subtitle)));
+  }
+
+  let mut data_files = packages.remove(&None).unwrap_or_default();
+  for (package, files) in packages {
+    data_files.push((package.unwrap().to_string_lossy().to_string(), zip_files(&files)));
+  }
+  fs::write(dir.join("data-pc.zip.bak"), zip_files(&data_files)).unwrap();
+
+  let subtitles: String = logs.iter()
+    .map(|log| format!(": {}\r\n\r\nText of {}\r\n\r\n\r\n", log.subtitle, log.subtitle))
+    .collect();
+  fs::write(dir.join("data").join("strings").join("en.subtitles.bak"), subtitles).unwrap();
+
+  dir
+}
+
+fn read(path: &Path) -> String {
+  fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn original_logs_are_shuffled_between_slots() {
+  let logs = DataStore::get_logs().unwrap();
+  let dir = witness_dir("shuffle", &logs);
+
+  let logs_dir = witness_data::extract_original_logs(&dir).unwrap();
+  for log in &logs {
+    let ogg = logs_dir.join(&log.filename).with_extension("ogg");
+    assert_eq!(read(&ogg), log.subtitle);
+    assert_eq!(read(&ogg.with_extension("sub")), format!("Text of {}", log.subtitle));
+  }
+
+  let placements = randomizer::place(7, &logs_dir, &RandomizerOptions::default()).unwrap();
+  let slots: BTreeSet<String> = placements.iter().map(|placement| placement.slot.subtitle.clone()).collect();
+  let sources: BTreeSet<String> = placements.iter().map(|placement| read(&placement.audio)).collect();
+
+  assert_eq!(placements.len(), logs.len());
+  assert_eq!(slots, sources);
+  assert!(placements.iter().any(|placement| read(&placement.audio) != placement.slot.subtitle));
+
+  witness_data::remove_original_logs(&dir).unwrap();
+  assert!(!logs_dir.exists());
+}
+
+#[test]
+fn custom_logs_are_mixed_with_originals() {
+  let logs = DataStore::get_logs().unwrap();
+  let dir = witness_dir("mix", &logs);
+
+  let custom_dir = dir.join("custom");
+  fs::create_dir_all(&custom_dir).unwrap();
+  for i in 0..40 {
+    fs::write(custom_dir.join(format!("custom_{:02}.ogg", i)), "custom").unwrap();
+  }
+
+  let options = RandomizerOptions {
+    originals_dir: Some(witness_data::extract_original_logs(&dir).unwrap()),
+    custom_share: "30%".parse().unwrap(),
+    ..Default::default()
+  };
+
+  let placements = randomizer::place(7, &custom_dir, &options).unwrap();
+  let custom_count = placements.iter().filter(|placement| read(&placement.audio) == "custom").count();
+
+  assert_eq!(placements.len(), logs.len());
+  assert_eq!(custom_count, CustomShare::Ratio(0.3).count_of(logs.len()));
+}
+
+#[test]
+fn custom_shares_parse() {
+  assert_eq!("all".parse::<CustomShare>().unwrap(), CustomShare::All);
+  assert_eq!("12".parse::<CustomShare>().unwrap(), CustomShare::Count(12));
+  assert_eq!(" 30% ".parse::<CustomShare>().unwrap(), CustomShare::Ratio(0.3));
+  assert_eq!(CustomShare::Count(80).count_of(49), 49);
+  assert_eq!(CustomShare::Ratio(0.5).count_of(49), 25);
+  assert!("120%".parse::<CustomShare>().is_err());
+  assert!("-3".parse::<CustomShare>().is_err());
+}
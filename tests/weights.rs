@@ -0,0 +1,75 @@
+use std::fs;
+
+use witness_audio_randomizer::core::{
+  randomizer::{self, RandomizerOptions},
+  seed,
+  weights::Weight,
+};
+
+mod common;
+use common::TempDir;
+
+fn logs_dir(name: &str) -> TempDir {
+  let dir = TempDir::new(&format!("weights_{}", name));
+
+  // More logs than there are slots, so that weights decide which ones get placed
+  for i in 0..80 {
+    fs::write(dir.join(format!("filler_{:02}.ogg", i)), b"OggS").unwrap();
+  }
+  fs::write(dir.join("marquee.ogg"), b"OggS").unwrap();
+  fs::write(dir.join("marquee.weight"), b"always\n").unwrap();
+  fs::write(dir.join("never.ogg"), b"OggS").unwrap();
+  fs::write(dir.join("weights.json"), br#"{"never.ogg": 0, "filler_00": 1000, "filler_01": "0.5"}"#).unwrap();
+
+  dir
+}
+
+#[test]
+fn weights_parse() {
+  assert_eq!("always".parse::<Weight>().unwrap(), Weight::Always);
+  assert_eq!(" 2.5\r\n".parse::<Weight>().unwrap(), Weight::Relative(2.5));
+  assert!("-1".parse::<Weight>().is_err());
+  assert!("NaN".parse::<Weight>().is_err());
+  assert!("lots".parse::<Weight>().is_err());
+}
+
+#[test]
+fn weights_drive_selection() {
+  let dir = logs_dir("selection");
+
+  for seed_string in ["1", "2", "3", "weighted"] {
+    let placements = randomizer::place(seed::seed_from_str(seed_string), &dir, &RandomizerOptions::default()).unwrap();
+    let names: Vec<String> = placements.iter()
+      .map(|placement| placement.audio.file_name().unwrap().to_string_lossy().to_string())
+      .collect();
+
+    assert_eq!(placements.len(), 50);
+    assert!(names.contains(&"marquee.ogg".to_owned()));
+    assert!(names.contains(&"filler_00.ogg".to_owned()));
+    assert!(!names.contains(&"never.ogg".to_owned()));
+
+    let again = randomizer::place(seed::seed_from_str(seed_string), &dir, &RandomizerOptions::default()).unwrap();
+    assert!(placements.iter().zip(again.iter()).all(|(a, b)| a.audio == b.audio && a.slot.filename == b.slot.filename));
+  }
+}
+
+#[test]
+fn weights_are_keyed_by_relative_path() {
+  let dir = logs_dir("paths");
+  for folder in ["first", "second"] {
+    fs::create_dir(dir.join(folder)).unwrap();
+  }
+  fs::write(dir.join("first").join("intro.OGG"), b"OggS").unwrap();
+  fs::write(dir.join("second").join("intro.oga"), b"OggS").unwrap();
+  fs::write(
+    dir.join("weights.json"),
+    br#"{"never.ogg": 0, "first/intro.ogg": 0, "second/intro.OGA": "always"}"#,
+  ).unwrap();
+
+  for seed_string in ["1", "2", "paths"] {
+    let placements = randomizer::place(seed::seed_from_str(seed_string), &dir, &RandomizerOptions::default()).unwrap();
+
+    assert!(placements.iter().any(|placement| placement.audio == dir.join("second").join("intro.oga")));
+    assert!(!placements.iter().any(|placement| placement.audio == dir.join("first").join("intro.OGG")));
+  }
+}